@@ -4,7 +4,7 @@ use aurora_engine_types::{
     H256, U256,
 };
 use aurora_eth_connector::{
-    connector_impl::WithdrawResult,
+    connector_impl::{WithdrawBatchStatus, WithdrawResult},
     deposit_event::{DepositedEvent, TokenMessageData, DEPOSITED_EVENT},
     fungible_token::storage_management::StorageBalance,
     log_entry,
@@ -381,6 +381,7 @@ async fn test_deposit_with_0x_prefix() -> anyhow::Result<()> {
         token_message_data,
         amount: NEP141Wei::new(deposit_amount),
         fee,
+        message: None,
     };
 
     let event_schema = ethabi::Event {
@@ -1259,3 +1260,1873 @@ async fn test_storage_withdraw() -> anyhow::Result<()> {
     assert!(contract.check_error_message(res, "ERR_NO_AVAILABLE_BALANCE"));
     Ok(())
 }
+
+#[tokio::test]
+async fn test_deposit_json_with_hex_proof() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    // The proof is serialized back with `0x`-hex byte fields
+    let proof = near_sdk::serde_json::to_value(contract.get_proof(PROOF_DATA_NEAR))?;
+    assert!(proof["log_entry_data"].as_str().unwrap().starts_with("0x"));
+
+    let res = contract
+        .contract
+        .call("deposit_json")
+        .args_json(json!({ "proof": proof }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(
+        contract.call_is_used_proof(PROOF_DATA_NEAR).await?,
+        "Expected not to fail because the proof should have been already used",
+    );
+
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let balance = contract.get_eth_on_near_balance(&receiver_id).await?;
+    assert_eq!(balance.0, DEPOSITED_AMOUNT - DEPOSITED_FEE);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulate_deposit() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let proof = contract.get_proof(PROOF_DATA_NEAR);
+
+    let res = contract
+        .contract
+        .call("simulate_deposit")
+        .args_json(json!({ "proof": proof }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert!(res["error"].is_null());
+    assert_eq!(res["kind"], "near");
+    assert_eq!(res["recipient"], DEPOSITED_RECIPIENT);
+    assert_eq!(res["amount"], DEPOSITED_AMOUNT.to_string());
+    assert_eq!(res["fee"], DEPOSITED_FEE.to_string());
+    // The legacy event has no message
+    assert!(res["message"].is_null());
+    assert_eq!(
+        res["finish_deposit_args"]["new_owner_id"],
+        DEPOSITED_RECIPIENT
+    );
+
+    contract.call_deposit_eth_to_near().await?;
+    let res = contract
+        .contract
+        .call("simulate_deposit")
+        .args_json(json!({ "proof": proof }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(res["error"], "ERR_PROOF_EXIST");
+    assert!(res["finish_deposit_args"].is_null());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulate_deposit_wrong_custodian_address() -> anyhow::Result<()> {
+    let contract =
+        TestContract::new_with_custodian("0000000000000000000000000000000000000001").await?;
+    let res = contract
+        .contract
+        .call("simulate_deposit")
+        .args_json(json!({ "proof": contract.get_proof(PROOF_DATA_NEAR) }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(res["error"], "ERR_WRONG_EVENT_ADDRESS");
+    assert_eq!(res["recipient"], DEPOSITED_RECIPIENT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_with_fee_policy_clamp_and_protocol_fee() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let treasury = contract.create_sub_account("treasury").await?;
+    let res = contract
+        .contract
+        .call("set_fee_policy")
+        .args_json(json!({
+            "policy": {
+                "max_fee": "200",
+                "max_fee_bps": null,
+                "min_net_deposit": null,
+                "protocol_fee_bps": 2500,
+                "treasury_id": treasury.id(),
+                "mode": "clamp",
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    contract.call_deposit_eth_to_near().await?;
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let balance = contract.get_eth_on_near_balance(&receiver_id).await?;
+    assert_eq!(balance.0, DEPOSITED_AMOUNT - 200);
+    let balance = contract
+        .get_eth_on_near_balance(contract.contract.id())
+        .await?;
+    assert_eq!(balance.0, 150);
+    let balance = contract.get_eth_on_near_balance(treasury.id()).await?;
+    assert_eq!(balance.0, 50);
+
+    let balance = contract.total_supply().await?;
+    assert_eq!(balance.0, DEPOSITED_AMOUNT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_with_fee_policy_reject() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_fee_policy")
+        .args_json(json!({
+            "policy": {
+                "max_fee": "200",
+                "max_fee_bps": null,
+                "min_net_deposit": null,
+                "protocol_fee_bps": 0,
+                "treasury_id": null,
+                "mode": "reject",
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let proof = contract.get_proof(PROOF_DATA_NEAR);
+    let res = contract.deposit_with_proof(&proof).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_FEE_TOO_HIGH"));
+    assert!(!contract.call_is_used_proof(PROOF_DATA_NEAR).await?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_fee_policy_without_treasury() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_fee_policy")
+        .args_json(json!({
+            "policy": {
+                "max_fee": null,
+                "max_fee_bps": null,
+                "min_net_deposit": null,
+                "protocol_fee_bps": 100,
+                "treasury_id": null,
+                "mode": "reject",
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_TREASURY_NOT_SET"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_with_relayer_allowlist() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let user_acc = contract.create_sub_account("relayer").await?;
+    // Allowlisted relayers submit deposits without the access right
+    let res = contract
+        .contract
+        .call("set_relayer_allowlist_mode")
+        .args_json(json!({ "enabled": true }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let proof = contract.get_proof(PROOF_DATA_NEAR);
+    let res = contract.user_deposit_with_proof(&user_acc, &proof).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_RELAYER_NOT_REGISTERED"));
+
+    let stake = near_units::parse_near!("1 N");
+    let res = user_acc
+        .call(contract.contract.id(), "register_relayer")
+        .deposit(stake)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = contract.user_deposit_with_proof(&user_acc, &proof).await?;
+    assert!(res.is_success());
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_FEE
+    );
+
+    let relayer = contract
+        .contract
+        .call("get_relayer")
+        .args_json(json!({ "account_id": user_acc.id() }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(relayer["stake"], stake.to_string());
+    assert_eq!(relayer["deposits_submitted"], "1");
+    assert_eq!(relayer["fees_earned"], DEPOSITED_FEE.to_string());
+
+    let res = user_acc
+        .call(contract.contract.id(), "unregister_relayer")
+        .deposit(ONE_YOCTO)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The unbonding relayer can't submit deposits and withdraw the stake
+    let res = contract.user_deposit_with_proof(&user_acc, &proof).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_RELAYER_UNBONDING"));
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_relayer_stake")
+        .deposit(ONE_YOCTO)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_RELAYER_STAKE_LOCKED"));
+
+    // Registering again cancels the unbonding
+    let res = contract
+        .contract
+        .call("set_relayer_unbonding_blocks")
+        .args_json(json!({ "unbonding_blocks": "0" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = user_acc
+        .call(contract.contract.id(), "register_relayer")
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = user_acc
+        .call(contract.contract.id(), "unregister_relayer")
+        .deposit(ONE_YOCTO)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_relayer_stake")
+        .deposit(ONE_YOCTO)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(res.json::<U128>()?.0, stake);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_register_relayer_with_low_stake() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let user_acc = contract.create_sub_account("relayer").await?;
+    let res = contract
+        .contract
+        .call("set_relayer_min_stake")
+        .args_json(json!({ "min_stake": near_units::parse_near!("1 N").to_string() }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = user_acc
+        .call(contract.contract.id(), "register_relayer")
+        .deposit(near_units::parse_near!("0.5 N"))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_RELAYER_STAKE_TOO_LOW"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_over_mint_limit_is_held() -> anyhow::Result<()> {
+    use aurora_eth_connector::admin_controlled::{PausedMask, PAUSE_DEPOSIT};
+
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_mint_limits")
+        .args_json(json!({
+            "limits": {
+                "window_blocks": "100",
+                "max_window_mint": "1000",
+                "max_single_deposit": null,
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    contract.call_deposit_eth_to_near().await?;
+    assert!(contract.call_is_used_proof(PROOF_DATA_NEAR).await?);
+
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let balance = contract.get_eth_on_near_balance(&receiver_id).await?;
+    assert_eq!(balance.0, 0);
+    let balance = contract.total_supply().await?;
+    assert_eq!(balance.0, 0);
+
+    let paused_flags: PausedMask = contract
+        .contract
+        .call("get_paused_flags")
+        .view()
+        .await?
+        .borsh()?;
+    assert_eq!(paused_flags, PAUSE_DEPOSIT);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_release_and_reject_held_deposit() -> anyhow::Result<()> {
+    use aurora_eth_connector::admin_controlled::PausedMask;
+
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_mint_limits")
+        .args_json(json!({
+            "limits": {
+                "window_blocks": "0",
+                "max_window_mint": null,
+                "max_single_deposit": "1000",
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    contract.call_deposit_eth_to_near().await?;
+    let pending = contract
+        .contract
+        .call("get_pending_deposits")
+        .args_json(json!({}))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    let pending = pending.as_array().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["reason"], "single_deposit_limit");
+    // The single deposit limit holds the deposit without pausing the deposits
+    let paused_flags: PausedMask = contract
+        .contract
+        .call("get_paused_flags")
+        .view()
+        .await?
+        .borsh()?;
+    assert_eq!(paused_flags, 0);
+    assert_eq!(
+        pending[0]["deposit_call"]["amount"],
+        DEPOSITED_AMOUNT.to_string()
+    );
+    let proof_key = pending[0]["deposit_call"]["proof_key"].clone();
+
+    // Replay of the held deposit is refused
+    let proof = contract.get_proof(PROOF_DATA_NEAR);
+    let res = contract.deposit_with_proof(&proof).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_PROOF_EXIST"));
+
+    let res = contract
+        .contract
+        .call("release_deposit")
+        .args_json(json!({ "proof_key": proof_key }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let balance = contract.get_eth_on_near_balance(&receiver_id).await?;
+    assert_eq!(balance.0, DEPOSITED_AMOUNT - DEPOSITED_FEE);
+
+    let count = contract
+        .contract
+        .call("get_pending_deposits_count")
+        .view()
+        .await?
+        .json::<U64>()?;
+    assert_eq!(count.0, 0);
+
+    // The held deposit can only be released or rejected once
+    let res = contract
+        .contract
+        .call("reject_deposit")
+        .args_json(json!({ "proof_key": proof_key }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_PENDING_DEPOSIT_NOT_FOUND"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_to_denied_account_is_held() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("add_denied_account")
+        .args_json(json!({ "account_id": DEPOSITED_RECIPIENT }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let is_denied = contract
+        .contract
+        .call("is_account_denied")
+        .args_json(json!({ "account_id": DEPOSITED_RECIPIENT }))
+        .view()
+        .await?
+        .json::<bool>()?;
+    assert!(is_denied);
+
+    contract.call_deposit_eth_to_near().await?;
+    assert!(contract.call_is_used_proof(PROOF_DATA_NEAR).await?);
+    let pending = contract
+        .contract
+        .call("get_pending_deposits")
+        .args_json(json!({}))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    let pending = pending.as_array().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["reason"], "recipient_denied");
+
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let balance = contract.get_eth_on_near_balance(&receiver_id).await?;
+    assert_eq!(balance.0, 0);
+    let balance = contract.total_supply().await?;
+    assert_eq!(balance.0, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_history() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_deposit_history_config")
+        .args_json(json!({ "config": { "enabled": true, "max_per_account": 5 } }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    contract.call_deposit_eth_to_near().await?;
+    let deposits = contract
+        .contract
+        .call("deposits_of")
+        .args_json(json!({ "account_id": DEPOSITED_RECIPIENT }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    let deposits = deposits.as_array().unwrap();
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0]["recipient"], DEPOSITED_RECIPIENT);
+    assert_eq!(deposits[0]["amount"], DEPOSITED_AMOUNT.to_string());
+    assert_eq!(deposits[0]["fee"], DEPOSITED_FEE.to_string());
+    assert_eq!(deposits[0]["relayer_id"], contract.contract.id().as_str());
+    assert_eq!(
+        deposits[0]["sender"],
+        "79183fdbd80e2d8aea1acaa2f67bfb8a36d40a8d"
+    );
+
+    let deposit = contract
+        .contract
+        .call("deposit_by_proof_key")
+        .args_json(json!({ "proof_key": deposits[0]["proof_key"] }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(&deposit, &deposits[0]);
+
+    let deposits = contract
+        .contract
+        .call("deposits_of")
+        .args_json(json!({ "account_id": DEPOSITED_RECIPIENT, "from_index": "1" }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert!(deposits.as_array().unwrap().is_empty());
+
+    // The deposits to the engine are logged under the credited address
+    contract.call_deposit_eth_to_aurora().await?;
+    let deposits = contract
+        .contract
+        .call("deposits_of_address")
+        .args_json(json!({ "address": format!("0x{}", RECIPIENT_ETH_ADDRESS) }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    let deposits = deposits.as_array().unwrap();
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0]["recipient"], CONTRACT_ACC);
+    assert_eq!(deposits[0]["eth_recipient"], RECIPIENT_ETH_ADDRESS);
+    let deposits = contract
+        .contract
+        .call("deposits_of")
+        .args_json(json!({ "account_id": CONTRACT_ACC }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert!(deposits.as_array().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_withdrawal_ledger() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+
+    let withdraw_amount = NEP141Wei::new(100);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    for expected_nonce in 0..2 {
+        let res = contract
+            .contract
+            .call("withdraw")
+            .args_borsh((contract.contract.id(), recipient_addr, withdraw_amount))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+        let data: WithdrawResult = res.borsh()?;
+        assert_eq!(data.nonce, expected_nonce);
+    }
+
+    let withdrawal = contract
+        .contract
+        .call("get_withdrawal")
+        .args_json(json!({ "nonce": "1" }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(withdrawal["nonce"], "1");
+    assert_eq!(withdrawal["sender_id"], contract.contract.id().as_str());
+    assert_eq!(withdrawal["recipient_address"], recipient_addr.encode());
+    assert_eq!(withdrawal["amount"], "100");
+    assert_eq!(
+        withdrawal["eth_custodian_address"],
+        validate_eth_address(CUSTODIAN_ADDRESS).encode()
+    );
+
+    let withdrawals = contract
+        .contract
+        .call("withdrawals_of")
+        .args_json(json!({ "account_id": contract.contract.id() }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    let nonces: Vec<_> = withdrawals
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|withdrawal| withdrawal["nonce"].clone())
+        .collect();
+    assert_eq!(nonces, vec!["0", "1"]);
+
+    let withdrawal = contract
+        .contract
+        .call("get_withdrawal")
+        .args_json(json!({ "nonce": "2" }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert!(withdrawal.is_null());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_withdraw_to_eth_by_user() -> anyhow::Result<()> {
+    use aurora_eth_connector::admin_controlled::PAUSE_WITHDRAW;
+
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+
+    let withdraw_amount = NEP141Wei::new(100);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_to_eth")
+        .args_borsh((recipient_addr, withdraw_amount))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "Requires attached deposit of exactly 1 yoctoNEAR"));
+
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_to_eth")
+        .args_borsh((recipient_addr, withdraw_amount))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let data: WithdrawResult = res.borsh()?;
+    assert_eq!(data.recipient_id, recipient_addr);
+    assert_eq!(data.amount, withdraw_amount);
+    assert_eq!(
+        data.eth_custodian_address,
+        validate_eth_address(CUSTODIAN_ADDRESS)
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE - withdraw_amount.as_u128()
+    );
+    assert_eq!(
+        contract.total_supply().await?.0,
+        DEPOSITED_AMOUNT - withdraw_amount.as_u128()
+    );
+
+    let res = contract
+        .contract
+        .call("set_paused_flags")
+        .args_borsh(PAUSE_WITHDRAW)
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_to_eth")
+        .args_borsh((recipient_addr, withdraw_amount))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "WithdrawErrorPaused"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_withdraw_with_fee() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+    let collector = contract.create_sub_account("collector").await?;
+
+    let res = contract
+        .contract
+        .call("set_withdraw_fee_schedule")
+        .args_json(json!({
+            "schedule": {
+                "flat_fee": "10",
+                "fee_bps": 100,
+                "collector_id": collector.id(),
+            }
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let withdraw_amount = NEP141Wei::new(1000);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_to_eth")
+        .args_borsh((recipient_addr, withdraw_amount))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(res
+        .logs()
+        .iter()
+        .any(|log| log.contains("ft_transfer") && log.contains("withdraw fee")));
+    let data: WithdrawResult = res.borsh()?;
+    assert_eq!(data.fee, NEP141Wei::new(20));
+    assert_eq!(data.amount, NEP141Wei::new(980));
+
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE - withdraw_amount.as_u128()
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(collector.id()).await?.0,
+        20
+    );
+    assert_eq!(contract.total_supply().await?.0, DEPOSITED_AMOUNT - 980);
+
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_to_eth")
+        .args_borsh((recipient_addr, NEP141Wei::new(10)))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_WITHDRAW_FEE_TOO_HIGH"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delayed_withdraw() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+    let guardian = contract.create_sub_account("guardian").await?;
+    let user_balance = DEPOSITED_AMOUNT - DEPOSITED_FEE;
+
+    let set_delay = |delay_blocks: u64| {
+        contract
+            .contract
+            .call("set_withdraw_delay_config")
+            .args_json(json!({
+                "config": {
+                    "threshold": "1000",
+                    "delay_blocks": delay_blocks.to_string(),
+                    "guardian_id": guardian.id(),
+                }
+            }))
+            .gas(DEFAULT_GAS)
+            .transact()
+    };
+    assert!(set_delay(1000).await?.is_success());
+
+    let withdraw_amount = NEP141Wei::new(5000);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let withdraw = || {
+        user_acc
+            .call(contract.contract.id(), "withdraw_to_eth")
+            .args_borsh((recipient_addr, withdraw_amount))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+    let res = withdraw().await?;
+    assert!(res.is_success());
+    // The queued withdrawal has no result to be used as a withdrawal proof
+    assert!(res.raw_bytes()?.is_empty());
+    let queued = contract
+        .contract
+        .call("get_queued_withdrawal")
+        .args_json(json!({ "nonce": "0" }))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(queued["amount"], withdraw_amount.as_u128().to_string());
+    assert_eq!(queued["fee"], "0");
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance - withdraw_amount.as_u128()
+    );
+    assert_eq!(contract.total_supply().await?.0, DEPOSITED_AMOUNT);
+
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_call")
+        .args_borsh((
+            user_acc.id(),
+            recipient_addr,
+            withdraw_amount,
+            "notify".to_string(),
+        ))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_WITHDRAW_DELAYED"));
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance - withdraw_amount.as_u128()
+    );
+    assert_eq!(contract.total_supply().await?.0, DEPOSITED_AMOUNT);
+
+    let res = user_acc
+        .call(contract.contract.id(), "finalize_withdraw")
+        .args_json(json!({ "nonce": "0" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_NOT_ALLOWED:TOO_EARLY"));
+
+    let res = user_acc
+        .call(contract.contract.id(), "cancel_withdraw")
+        .args_json(json!({ "nonce": "0" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_NOT_GUARDIAN"));
+
+    let res = guardian
+        .call(contract.contract.id(), "cancel_withdraw")
+        .args_json(json!({ "nonce": "0" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance
+    );
+
+    // Without delay the queued withdrawal can be finalized right away
+    assert!(set_delay(0).await?.is_success());
+    let res = withdraw().await?;
+    assert!(res.is_success());
+    assert!(res.raw_bytes()?.is_empty());
+
+    let res = user_acc
+        .call(contract.contract.id(), "finalize_withdraw")
+        .args_json(json!({ "nonce": "1" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let data: WithdrawResult = res.borsh()?;
+    assert_eq!(data.amount, withdraw_amount);
+    assert_eq!(data.nonce, 1);
+    assert_eq!(data.recipient_id, recipient_addr);
+    assert_eq!(
+        contract.total_supply().await?.0,
+        DEPOSITED_AMOUNT - withdraw_amount.as_u128()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_withdraw_call() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+    let receiver_acc = contract.create_sub_account("withdraw_receiver").await?;
+
+    let withdraw_amount = NEP141Wei::new(100);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let res = user_acc
+        .call(contract.contract.id(), "withdraw_call")
+        .args_borsh((
+            receiver_acc.id(),
+            recipient_addr,
+            withdraw_amount,
+            "notify".to_string(),
+        ))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    // The receiver account has no `on_withdraw`, the withdrawal is finished anyway
+    let receiver_log = format!(r#""receiver_id":"{}""#, receiver_acc.id());
+    assert!(res.logs().iter().any(|log| log.contains("withdraw_call")
+        && log.contains(&receiver_log)
+        && log.contains(r#""notified":false"#)));
+    let data: WithdrawResult = res.borsh()?;
+    assert_eq!(data.recipient_id, recipient_addr);
+    assert_eq!(data.amount, withdraw_amount);
+
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE - withdraw_amount.as_u128()
+    );
+    assert_eq!(
+        contract.total_supply().await?.0,
+        DEPOSITED_AMOUNT - withdraw_amount.as_u128()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_withdraw_batch() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+    let user_balance = DEPOSITED_AMOUNT - DEPOSITED_FEE;
+
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let withdrawals = vec![
+        (user_acc.id().clone(), recipient_addr, NEP141Wei::new(100)),
+        (
+            user_acc.id().clone(),
+            recipient_addr,
+            NEP141Wei::new(user_balance),
+        ),
+        (user_acc.id().clone(), recipient_addr, NEP141Wei::new(200)),
+    ];
+
+    // Without the isolation the whole batch fails
+    let res = contract
+        .contract
+        .call("withdraw_batch")
+        .args_borsh((withdrawals.clone(), false))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance
+    );
+
+    let res = contract
+        .contract
+        .call("withdraw_batch")
+        .args_borsh((withdrawals, true))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert_eq!(
+        res.logs()
+            .iter()
+            .filter(|log| log.contains("ft_burn"))
+            .count(),
+        1
+    );
+    let data: Vec<WithdrawBatchStatus> = res.borsh()?;
+    assert_eq!(data.len(), 3);
+    assert!(
+        matches!(&data[0], WithdrawBatchStatus::Burned(result) if result.amount == NEP141Wei::new(100))
+    );
+    assert!(
+        matches!(&data[1], WithdrawBatchStatus::Failed { error } if error == "ERR_NOT_ENOUGH_BALANCE")
+    );
+    assert!(
+        matches!(&data[2], WithdrawBatchStatus::Burned(result) if result.amount == NEP141Wei::new(200))
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance - 300
+    );
+    assert_eq!(contract.total_supply().await?.0, DEPOSITED_AMOUNT - 300);
+
+    // The fee can't be transferred to the unregistered collector
+    let collector = contract.create_sub_account("collector").await?;
+    for (method, args) in [
+        (
+            "set_registration_policy",
+            json!({ "policy": "require_storage_deposit" }),
+        ),
+        (
+            "set_withdraw_fee_schedule",
+            json!({
+                "schedule": {
+                    "flat_fee": "10",
+                    "fee_bps": 0,
+                    "collector_id": collector.id(),
+                }
+            }),
+        ),
+    ] {
+        let res = contract
+            .contract
+            .call(method)
+            .args_json(args)
+            .gas(DEFAULT_GAS)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+    let withdrawals = vec![(user_acc.id().clone(), recipient_addr, NEP141Wei::new(100))];
+    let res = contract
+        .contract
+        .call("withdraw_batch")
+        .args_borsh((withdrawals, true))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let data: Vec<WithdrawBatchStatus> = res.borsh()?;
+    assert!(
+        matches!(&data[0], WithdrawBatchStatus::Failed { error } if error == "ERR_FEE_COLLECTOR_NOT_REGISTERED")
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        user_balance - 300
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_with_evm_call_fallback() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+
+    let eth_custodian_address: Address = Address::decode(CUSTODIAN_ADDRESS).unwrap();
+    let recipient_address = Address::from_array([10u8; 20]);
+    let evm_contract = Address::from_array([11u8; 20]);
+    let deposit_amount = 17;
+
+    // The receiver has no `ft_on_transfer`, so the EVM call and the fallback are refunded
+    let message = [
+        CONTRACT_ACC,
+        ":",
+        &recipient_address.encode(),
+        ":",
+        &evm_contract.encode(),
+        ":",
+        "deadbeef",
+    ]
+    .concat();
+    let event_schema = ethabi::Event {
+        name: DEPOSITED_EVENT.into(),
+        inputs: DepositedEvent::event_params(),
+        anonymous: false,
+    };
+    let log_entry = log_entry::LogEntry {
+        address: eth_custodian_address.raw(),
+        topics: vec![event_schema.signature(), H256::zero()],
+        data: ethabi::encode(&[
+            ethabi::Token::String(message),
+            ethabi::Token::Uint(U256::from(deposit_amount)),
+            ethabi::Token::Uint(U256::zero()),
+        ]),
+    };
+    let proof = Proof {
+        log_index: 1,
+        log_entry_data: rlp::encode(&log_entry).to_vec(),
+        receipt_index: 1,
+        receipt_data: Vec::new(),
+        header_data: Vec::new(),
+        proof: Vec::new(),
+    };
+
+    let res = contract.deposit_with_proof(&proof).await?;
+    assert!(res.is_success());
+    // Both the call with the EVM call and the fallback call were rejected
+    assert_eq!(res.receipt_failures().len(), 2);
+
+    // The refunded amount stays with the NEAR recipient of the deposit
+    let recipient_id: AccountId = CONTRACT_ACC.parse()?;
+    let recipient_balance = contract.get_eth_on_near_balance(&recipient_id).await?;
+    assert_eq!(recipient_balance.0, deposit_amount);
+    assert_eq!(contract.total_supply().await?.0, deposit_amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_v2_with_near_call_refund() -> anyhow::Result<()> {
+    use aurora_eth_connector::deposit_event::DEPOSITED_V2_EVENT;
+
+    let contract = TestContract::new().await?;
+    let eth_custodian_address: Address = Address::decode(CUSTODIAN_ADDRESS).unwrap();
+    let deposit_amount = 1000;
+    let fee = 10;
+
+    let event_schema = ethabi::Event {
+        name: DEPOSITED_V2_EVENT.into(),
+        inputs: DepositedEvent::event_v2_params(),
+        anonymous: false,
+    };
+    // The receiver has no `ft_on_transfer`, so the tokens are refunded to the owner
+    let log_entry = log_entry::LogEntry {
+        address: eth_custodian_address.raw(),
+        topics: vec![event_schema.signature(), H256::zero()],
+        data: ethabi::encode(&[
+            ethabi::Token::String(DEPOSITED_RECIPIENT.to_string()),
+            ethabi::Token::Address([0u8; 20].into()),
+            ethabi::Token::Uint(U256::from(deposit_amount)),
+            ethabi::Token::Uint(U256::from(fee)),
+            ethabi::Token::String("swap".to_string()),
+            ethabi::Token::String("dex.test.near".to_string()),
+        ]),
+    };
+    let proof = Proof {
+        log_index: 1,
+        log_entry_data: rlp::encode(&log_entry).to_vec(),
+        receipt_index: 1,
+        receipt_data: Vec::new(),
+        header_data: Vec::new(),
+        proof: Vec::new(),
+    };
+
+    let res = contract.deposit_with_proof(&proof).await?;
+    assert!(res.is_success());
+    assert!(res
+        .logs()
+        .iter()
+        .any(|log| log.contains("ft_transfer") && log.contains("dex.test.near")));
+
+    let owner_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    assert_eq!(
+        contract.get_eth_on_near_balance(&owner_id).await?.0,
+        deposit_amount - fee
+    );
+    assert_eq!(
+        contract
+            .get_eth_on_near_balance(contract.contract.id())
+            .await?
+            .0,
+        fee
+    );
+    assert_eq!(contract.total_supply().await?.0, deposit_amount);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_registry() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let silo = contract.create_sub_account("silo").await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+
+    // Unregistered engines have no access right
+    let withdraw_amount = NEP141Wei::new(100);
+    let recipient_addr = validate_eth_address(RECIPIENT_ETH_ADDRESS);
+    let withdraw = |sender_id: &AccountId| {
+        silo.call(contract.contract.id(), "withdraw")
+            .args_borsh((sender_id, recipient_addr, withdraw_amount))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+    let res = withdraw(user_acc.id()).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ACCESS_RIGHT"));
+
+    let res = contract
+        .contract
+        .call("register_engine")
+        .args_json(json!({ "engine_id": silo.id() }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = contract
+        .contract
+        .call("set_engine_paused_flags")
+        .args_json(json!({ "engine_id": silo.id(), "paused_mask": 2 }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = withdraw(silo.id()).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ENGINE_PAUSED"));
+
+    let res = contract
+        .contract
+        .call("set_engine_paused_flags")
+        .args_json(json!({ "engine_id": silo.id(), "paused_mask": 0 }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    // The engine withdraws the funds of other accounts only within their allowance
+    let res = withdraw(user_acc.id()).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INSUFFICIENT_ALLOWANCE"));
+    // The engine withdraws its own tokens, even if they weren't delivered with a transfer call
+    let res = contract
+        .contract
+        .call("ft_transfer")
+        .args_json((
+            silo.id(),
+            withdraw_amount.as_u128().to_string(),
+            "transfer memo",
+        ))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = withdraw(silo.id()).await?;
+    assert!(res.is_success());
+    assert_eq!(contract.get_eth_on_near_balance(silo.id()).await?.0, 0);
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE
+    );
+
+    // The engine has no access to the entry points of the account with access right
+    let res = silo
+        .call(contract.contract.id(), "ft_transfer")
+        .args_json(json!({ "receiver_id": user_acc.id(), "amount": "1" }))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ACCESS_RIGHT"));
+
+    // The engine moves the funds of other accounts only within their allowance
+    let res = silo
+        .call(contract.contract.id(), "engine_ft_transfer")
+        .args_json(json!({
+            "sender_id": user_acc.id(),
+            "receiver_id": silo.id(),
+            "amount": "1",
+        }))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INSUFFICIENT_ALLOWANCE"));
+
+    // The engine can't manage the storage of other accounts
+    let res = silo
+        .call(contract.contract.id(), "engine_storage_unregister")
+        .args_json(json!({ "sender_id": user_acc.id(), "force": true }))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ACCESS_RIGHT"));
+
+    let engines = contract
+        .contract
+        .call("get_engines")
+        .args_json(json!({}))
+        .view()
+        .await?
+        .json::<near_sdk::serde_json::Value>()?;
+    assert_eq!(engines.as_array().unwrap().len(), 1);
+    assert_eq!(engines[0]["engine_id"], silo.id().to_string());
+    assert_eq!(engines[0]["supply"], "0");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposit_to_unregistered_engine() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+
+    let eth_custodian_address: Address = Address::decode(CUSTODIAN_ADDRESS).unwrap();
+    let message = ["silo.near:", &Address::from_array([10u8; 20]).encode()].concat();
+    let event_schema = ethabi::Event {
+        name: DEPOSITED_EVENT.into(),
+        inputs: DepositedEvent::event_params(),
+        anonymous: false,
+    };
+    let log_entry = log_entry::LogEntry {
+        address: eth_custodian_address.raw(),
+        topics: vec![event_schema.signature(), H256::zero()],
+        data: ethabi::encode(&[
+            ethabi::Token::String(message),
+            ethabi::Token::Uint(U256::from(100)),
+            ethabi::Token::Uint(U256::zero()),
+        ]),
+    };
+    let proof = Proof {
+        log_index: 1,
+        log_entry_data: rlp::encode(&log_entry).to_vec(),
+        receipt_index: 1,
+        receipt_data: Vec::new(),
+        header_data: Vec::new(),
+        proof: Vec::new(),
+    };
+
+    let res = contract.deposit_with_proof(&proof).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ENGINE_NOT_REGISTERED"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_engine_allowance_mode() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+    let spender_acc = contract.create_sub_account("spender").await?;
+    let receiver_id = AccountId::try_from("receiver.root".to_string()).unwrap();
+
+    let res = contract
+        .contract
+        .call("set_engine_allowance_mode")
+        .args_json(json!({ "enabled": true }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The engine can't move the user funds without the allowance
+    let transfer_amount = 100;
+    let engine_transfer = || {
+        contract
+            .contract
+            .call("engine_ft_transfer")
+            .args_json((
+                user_acc.id(),
+                &receiver_id,
+                transfer_amount.to_string(),
+                "transfer memo",
+            ))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+    let res = engine_transfer().await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INSUFFICIENT_ALLOWANCE"));
+
+    for (spender_id, amount) in [
+        (contract.contract.id(), transfer_amount),
+        (spender_acc.id(), transfer_amount),
+    ] {
+        let res = user_acc
+            .call(contract.contract.id(), "ft_approve")
+            .args_json(json!({ "spender_id": spender_id, "amount": amount.to_string() }))
+            .gas(DEFAULT_GAS)
+            .deposit(near_units::parse_near!("0.01 N"))
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    let res = engine_transfer().await?;
+    assert!(res.is_success());
+    let allowance = contract
+        .contract
+        .call("ft_allowance")
+        .args_json(json!({ "owner_id": user_acc.id(), "spender_id": contract.contract.id() }))
+        .view()
+        .await?
+        .json::<U128>()?;
+    assert_eq!(allowance.0, 0);
+    let res = engine_transfer().await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INSUFFICIENT_ALLOWANCE"));
+
+    let transfer_from = |amount: u128| {
+        spender_acc
+            .call(contract.contract.id(), "ft_transfer_from")
+            .args_json(json!({
+                "owner_id": user_acc.id(),
+                "receiver_id": &receiver_id,
+                "amount": amount.to_string(),
+            }))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+    let res = transfer_from(transfer_amount + 1).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INSUFFICIENT_ALLOWANCE"));
+    let res = transfer_from(transfer_amount).await?;
+    assert!(res.is_success());
+
+    assert_eq!(
+        contract.get_eth_on_near_balance(user_acc.id()).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE - 2 * transfer_amount
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(&receiver_id).await?.0,
+        2 * transfer_amount
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ft_transfer_with_signature() -> anyhow::Result<()> {
+    use aurora_eth_connector::meta_transfer::TransferAuthorization;
+    use ed25519_dalek::Signer;
+
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let relayer_acc = contract.create_sub_account("relayer").await?;
+
+    // The owner is the implicit account of the signing key
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    let keypair = ed25519_dalek::Keypair { secret, public };
+    let owner_id = hex::encode(keypair.public.to_bytes());
+    let public_key = format!(
+        "ed25519:{}",
+        near_sdk::bs58::encode(keypair.public.to_bytes()).into_string()
+    );
+
+    let owner_amount = 200;
+    let res = contract
+        .contract
+        .call("ft_transfer")
+        .args_json((&owner_id, owner_amount.to_string(), "transfer memo"))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let authorization = TransferAuthorization {
+        contract_id: contract.contract.id().as_str().parse().unwrap(),
+        owner_id: owner_id.parse().unwrap(),
+        receiver_id: DEPOSITED_RECIPIENT.parse().unwrap(),
+        amount: 100.into(),
+        memo: None,
+        fee: 10.into(),
+        nonce: 0.into(),
+        valid_until_block: u64::MAX.into(),
+    };
+    let signature = keypair
+        .sign(&authorization.hash(&authorization.contract_id))
+        .to_bytes();
+    let transfer_with_signature = || {
+        relayer_acc
+            .call(contract.contract.id(), "ft_transfer_with_signature")
+            .args_json(json!({
+                "authorization": authorization,
+                "public_key": public_key,
+                "signature": near_sdk::json_types::Base64VecU8(signature.to_vec()),
+            }))
+            .gas(DEFAULT_GAS)
+            .transact()
+    };
+    // The fee is paid only to the registered account
+    let res = transfer_with_signature().await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ACCOUNT_NOT_REGISTERED"));
+    let res = contract
+        .contract
+        .call("storage_deposit")
+        .args_json(json!({ "account_id": relayer_acc.id() }))
+        .gas(DEFAULT_GAS)
+        .deposit(10)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = transfer_with_signature().await?;
+    assert!(res.is_success());
+
+    // The authorization can't be replayed
+    let res = transfer_with_signature().await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_INVALID_NONCE"));
+
+    let owner_id = AccountId::try_from(owner_id).unwrap();
+    assert_eq!(
+        contract.get_eth_on_near_balance(&owner_id).await?.0,
+        owner_amount - 110
+    );
+    assert_eq!(
+        contract.get_eth_on_near_balance(relayer_acc.id()).await?.0,
+        10
+    );
+    let nonce = contract
+        .contract
+        .call("ft_transfer_nonce_of")
+        .args_json(json!({ "account_id": owner_id }))
+        .view()
+        .await?
+        .json::<U64>()?;
+    assert_eq!(nonce.0, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ft_transfer_batch() -> anyhow::Result<()> {
+    use aurora_eth_connector::fungible_token::core::MAX_TRANSFER_BATCH_SIZE;
+
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+    let other_receiver_id = AccountId::try_from("receiver.root".to_string()).unwrap();
+    let transfer_batch = |transfers: near_sdk::serde_json::Value| {
+        contract
+            .contract
+            .call("ft_transfer_batch")
+            .args_json(json!({ "transfers": transfers }))
+            .gas(DEFAULT_GAS)
+            .deposit(ONE_YOCTO)
+            .transact()
+    };
+
+    let res = transfer_batch(json!([])).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_EMPTY_BATCH"));
+    let transfers = vec![json!([&receiver_id, "1", null]); MAX_TRANSFER_BATCH_SIZE + 1];
+    let res = transfer_batch(json!(transfers)).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_TOO_MANY_TRANSFERS"));
+
+    // Nothing is applied if one of the transfers fails
+    let res = transfer_batch(json!([
+        [&receiver_id, "100", null],
+        [contract.contract.id(), "100", null],
+    ]))
+    .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_SENDER_EQUALS_RECEIVER"));
+    let res = transfer_batch(json!([
+        [&receiver_id, "100", null],
+        [&other_receiver_id, DEPOSITED_FEE.to_string(), null],
+    ]))
+    .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_NOT_ENOUGH_BALANCE"));
+
+    let res = transfer_batch(json!([
+        [&receiver_id, "100", "batch memo"],
+        [&other_receiver_id, "50", null],
+    ]))
+    .await?;
+    assert!(res.is_success());
+    assert_eq!(
+        res.logs()
+            .iter()
+            .filter(|log| log.contains("ft_transfer"))
+            .count(),
+        1
+    );
+
+    assert_eq!(
+        contract.get_eth_on_near_balance(&receiver_id).await?.0,
+        DEPOSITED_AMOUNT - DEPOSITED_FEE + 100
+    );
+    assert_eq!(
+        contract
+            .get_eth_on_near_balance(&other_receiver_id)
+            .await?
+            .0,
+        50
+    );
+    assert_eq!(
+        contract
+            .get_eth_on_near_balance(contract.contract.id())
+            .await?
+            .0,
+        DEPOSITED_FEE - 150
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ft_balances_of() -> anyhow::Result<()> {
+    use aurora_eth_connector::fungible_token::core::{
+        AccountBalanceView, MAX_BALANCES_OF_ACCOUNTS,
+    };
+
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+
+    let account_ids = json!([DEPOSITED_RECIPIENT, "unknown.root", CONTRACT_ACC]);
+    let balances = contract
+        .contract
+        .call("ft_balances_of")
+        .args_json(json!({ "account_ids": account_ids }))
+        .view()
+        .await?
+        .json::<Vec<U128>>()?;
+    assert_eq!(
+        balances,
+        vec![
+            U128(DEPOSITED_AMOUNT - DEPOSITED_FEE),
+            U128(0),
+            U128(DEPOSITED_FEE)
+        ]
+    );
+
+    let balances = contract
+        .contract
+        .call("ft_balances_with_registration_of")
+        .args_json(json!({ "account_ids": account_ids }))
+        .view()
+        .await?
+        .json::<Vec<AccountBalanceView>>()?;
+    assert!(balances[0].registered);
+    assert!(!balances[1].registered);
+    assert_eq!(balances[2].balance, U128(DEPOSITED_FEE));
+
+    let account_ids = vec![DEPOSITED_RECIPIENT; MAX_BALANCES_OF_ACCOUNTS + 1];
+    let res = contract
+        .contract
+        .call("ft_balances_of")
+        .args_json(json!({ "account_ids": account_ids }))
+        .view()
+        .await;
+    assert!(format!("{:?}", res).contains("ERR_TOO_MANY_ACCOUNTS"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_balance_checkpoints() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+    let receiver_id = AccountId::try_from(DEPOSITED_RECIPIENT.to_string()).unwrap();
+
+    for (method, args) in [
+        ("set_checkpoint_tracking", json!({ "enabled": true })),
+        (
+            "set_account_checkpoint_tracking",
+            json!({ "account_id": &receiver_id, "enabled": true }),
+        ),
+    ] {
+        let res = contract
+            .contract
+            .call(method)
+            .args_json(args)
+            .gas(DEFAULT_GAS)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+    let since = contract
+        .contract
+        .call("get_checkpoint_tracking_since")
+        .args_json(json!({ "account_id": &receiver_id }))
+        .view()
+        .await?
+        .json::<Option<U64>>()?
+        .unwrap();
+
+    let transfer_amount = 70;
+    let res = contract
+        .contract
+        .call("ft_transfer")
+        .args_json((&receiver_id, transfer_amount.to_string(), "transfer memo"))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let balance_at = |account_id: AccountId, block_height: u64| {
+        contract
+            .contract
+            .call("ft_balance_at")
+            .args_json(
+                json!({ "account_id": account_id, "block_height": block_height.to_string() }),
+            )
+            .view()
+    };
+    let balance = balance_at(receiver_id.clone(), since.0)
+        .await?
+        .json::<Option<U128>>()?;
+    assert_eq!(balance, Some(U128(DEPOSITED_AMOUNT - DEPOSITED_FEE)));
+    let balance = balance_at(receiver_id.clone(), since.0 - 1)
+        .await?
+        .json::<Option<U128>>()?;
+    assert_eq!(balance, None);
+    let balance = balance_at(contract.contract.id().clone(), since.0)
+        .await?
+        .json::<Option<U128>>()?;
+    assert_eq!(balance, Some(U128(DEPOSITED_FEE)));
+
+    let total_supply = contract
+        .contract
+        .call("ft_total_supply_at")
+        .args_json(json!({ "block_height": since }))
+        .view()
+        .await?
+        .json::<Option<U128>>()?;
+    assert_eq!(total_supply, Some(U128(DEPOSITED_AMOUNT)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_registration_policy() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    contract.call_deposit_eth_to_near().await?;
+
+    let set_policy = |policy: &'static str| {
+        contract
+            .contract
+            .call("set_registration_policy")
+            .args_json(json!({ "policy": policy }))
+            .gas(DEFAULT_GAS)
+            .transact()
+    };
+    let transfer = |receiver_id: &'static str, deposit: u128| {
+        contract
+            .contract
+            .call("ft_transfer")
+            .args_json((receiver_id, "10", "transfer memo"))
+            .gas(DEFAULT_GAS)
+            .deposit(deposit)
+            .transact()
+    };
+
+    let res = set_policy("require_storage_deposit").await?;
+    assert!(res.is_success());
+    let res = transfer("receiver.root", ONE_YOCTO).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ACCOUNT_NOT_REGISTERED"));
+
+    let res = contract
+        .contract
+        .call("storage_deposit")
+        .args_json(json!({ "account_id": "receiver.root" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    let res = transfer("receiver.root", ONE_YOCTO).await?;
+    assert!(res.is_success());
+
+    let res = set_policy("charge_sender").await?;
+    assert!(res.is_success());
+    let res = transfer("other_receiver.root", ONE_YOCTO).await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_ATTACHED_DEPOSIT_NOT_ENOUGH"));
+    let balance_before = contract.contract.view_account().await?.balance;
+    let res = transfer("other_receiver.root", near_units::parse_near!("1 N")).await?;
+    assert!(res.is_success());
+    // The deposit above the registration cost is refunded
+    let balance_after = contract.contract.view_account().await?.balance;
+    assert!(balance_before - balance_after < near_units::parse_near!("0.1 N"));
+
+    let policy = contract
+        .contract
+        .call("get_registration_policy")
+        .view()
+        .await?
+        .json::<String>()?;
+    assert_eq!(policy, "charge_sender");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_charge_sender_without_attached_deposit() -> anyhow::Result<()> {
+    use aurora_eth_connector::meta_transfer::TransferAuthorization;
+    use ed25519_dalek::Signer;
+
+    let contract = TestContract::new().await?;
+    let res = contract
+        .contract
+        .call("set_registration_policy")
+        .args_json(json!({ "policy": "charge_sender" }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let relayer_acc = contract.create_sub_account("relayer").await?;
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    let keypair = ed25519_dalek::Keypair { secret, public };
+    let owner_id = hex::encode(keypair.public.to_bytes());
+    for account_id in [
+        DEPOSITED_RECIPIENT,
+        relayer_acc.id().as_str(),
+        owner_id.as_str(),
+    ] {
+        let res = contract
+            .contract
+            .call("storage_deposit")
+            .args_json(json!({ "account_id": account_id }))
+            .gas(DEFAULT_GAS)
+            .deposit(10)
+            .transact()
+            .await?;
+        assert!(res.is_success());
+    }
+
+    // The deposits are minted and transferred by the contract without attached deposit
+    contract.call_deposit_eth_to_near().await?;
+    contract.call_deposit_eth_to_aurora().await?;
+    assert_eq!(
+        contract.total_supply().await?.0,
+        DEPOSITED_AMOUNT + DEPOSITED_EVM_AMOUNT
+    );
+
+    let res = contract
+        .contract
+        .call("ft_transfer")
+        .args_json((&owner_id, "200", "transfer memo"))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    // The signed transfer is relayed without attached deposit
+    let authorization = TransferAuthorization {
+        contract_id: contract.contract.id().as_str().parse().unwrap(),
+        owner_id: owner_id.parse().unwrap(),
+        receiver_id: DEPOSITED_RECIPIENT.parse().unwrap(),
+        amount: 100.into(),
+        memo: None,
+        fee: 10.into(),
+        nonce: 0.into(),
+        valid_until_block: u64::MAX.into(),
+    };
+    let signature = keypair
+        .sign(&authorization.hash(&authorization.contract_id))
+        .to_bytes();
+    let res = relayer_acc
+        .call(contract.contract.id(), "ft_transfer_with_signature")
+        .args_json(json!({
+            "authorization": authorization,
+            "public_key": format!(
+                "ed25519:{}",
+                near_sdk::bs58::encode(keypair.public.to_bytes()).into_string()
+            ),
+            "signature": near_sdk::json_types::Base64VecU8(signature.to_vec()),
+        }))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let owner_id = AccountId::try_from(owner_id).unwrap();
+    assert_eq!(contract.get_eth_on_near_balance(&owner_id).await?.0, 90);
+    assert_eq!(
+        contract.get_eth_on_near_balance(relayer_acc.id()).await?.0,
+        10
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_storage_unregister() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let user_acc = contract.create_sub_account("eth_recipient").await?;
+
+    let res = contract
+        .contract
+        .call("set_access_right")
+        .args_json((user_acc.id(),))
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = user_acc
+        .call(contract.contract.id(), "storage_deposit")
+        .args_json(json!({}))
+        .gas(DEFAULT_GAS)
+        .deposit(10)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+
+    let res = user_acc
+        .call(contract.contract.id(), "storage_unregister")
+        .args_json(json!({}))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(res.json::<bool>()?);
+
+    // The account is already unregistered
+    let res = user_acc
+        .call(contract.contract.id(), "storage_unregister")
+        .args_json(json!({}))
+        .gas(DEFAULT_GAS)
+        .deposit(ONE_YOCTO)
+        .transact()
+        .await?;
+    assert!(res.is_success());
+    assert!(!res.json::<bool>()?);
+    Ok(())
+}
@@ -27,6 +27,38 @@ async fn test_migration_access_right() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_migrate_state() -> anyhow::Result<()> {
+    let contract = TestContract::new().await?;
+    let version = contract
+        .contract
+        .call("get_state_version")
+        .view()
+        .await?
+        .json::<u32>()?;
+    assert_eq!(version, aurora_eth_connector::migration::STATE_VERSION);
+
+    let user_acc = contract.create_sub_account("any").await?;
+    let res = user_acc
+        .call(contract.contract.id(), "migrate_state")
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "Method migrate_state is private"));
+
+    // The state of the new contract already has the current layout
+    let res = contract
+        .contract
+        .call("migrate_state")
+        .gas(DEFAULT_GAS)
+        .transact()
+        .await?;
+    assert!(res.is_failure());
+    assert!(contract.check_error_message(res, "ERR_STATE_ALREADY_MIGRATED"));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_migration() -> anyhow::Result<()> {
     let contract = TestContract::new().await?;
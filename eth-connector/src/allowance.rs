@@ -0,0 +1,156 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    ext_contract,
+    json_types::U128,
+    AccountId, Balance, IntoStorageKey,
+};
+
+/// Spending allowances given by token owners to other accounts.
+/// While `engine_allowance_mode` is on, the engine transfers on behalf of another
+/// account draw down the allowance given by that account to the engine.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Allowances {
+    /// Allowances indexed by `(owner_id, spender_id)`.
+    pub allowances: LookupMap<(AccountId, AccountId), Balance>,
+    pub engine_allowance_mode: bool,
+}
+
+impl Allowances {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            allowances: LookupMap::new(prefix),
+            engine_allowance_mode: false,
+        }
+    }
+
+    pub fn get(&self, owner_id: &AccountId, spender_id: &AccountId) -> Balance {
+        self.allowances
+            .get(&(owner_id.clone(), spender_id.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Set the allowance of the spender. Zero amount removes the allowance.
+    pub fn approve(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), error::AllowanceError> {
+        if owner_id == spender_id {
+            return Err(error::AllowanceError::SelfApproval);
+        }
+        let key = (owner_id.clone(), spender_id.clone());
+        if amount == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &amount);
+        }
+        Ok(())
+    }
+
+    /// Draw down the allowance of the spender and return the remaining allowance.
+    pub fn spend(
+        &mut self,
+        owner_id: &AccountId,
+        spender_id: &AccountId,
+        amount: Balance,
+    ) -> Result<Balance, error::AllowanceError> {
+        let remaining = self
+            .get(owner_id, spender_id)
+            .checked_sub(amount)
+            .ok_or(error::AllowanceError::InsufficientAllowance)?;
+        let key = (owner_id.clone(), spender_id.clone());
+        if remaining == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &remaining);
+        }
+        Ok(remaining)
+    }
+}
+
+#[ext_contract(ext_ft_allowance)]
+pub trait FungibleTokenAllowance {
+    /// Set the amount of the predecessor tokens the spender is allowed to transfer.
+    /// Zero amount revokes the allowance. Requires at least 1 yoctoNEAR attached,
+    /// and the storage cost of the new allowance record. The storage cost of the revoked
+    /// allowance is refunded.
+    fn ft_approve(&mut self, spender_id: AccountId, amount: U128);
+
+    fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128;
+
+    /// Transfer tokens of the owner within the allowance given to the predecessor.
+    /// Requires exactly 1 yoctoNEAR attached.
+    fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    );
+
+    /// Turn on drawing down the allowances in the engine transfers.
+    fn set_engine_allowance_mode(&mut self, enabled: bool);
+
+    fn is_engine_allowance_mode(&self) -> bool;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum AllowanceError {
+        InsufficientAllowance,
+        SelfApproval,
+    }
+
+    impl AsRef<[u8]> for AllowanceError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::InsufficientAllowance => errors::ERR_INSUFFICIENT_ALLOWANCE,
+                Self::SelfApproval => errors::ERR_SELF_APPROVAL,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_approve_and_spend() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut allowances = Allowances::new(b"a".to_vec());
+        let alice = account("alice");
+        let aurora = account("aurora");
+        assert!(matches!(
+            allowances.approve(&alice, &alice, 100),
+            Err(error::AllowanceError::SelfApproval)
+        ));
+
+        allowances.approve(&alice, &aurora, 100).unwrap();
+        assert_eq!(allowances.spend(&alice, &aurora, 40).unwrap(), 60);
+        assert!(matches!(
+            allowances.spend(&alice, &aurora, 61),
+            Err(error::AllowanceError::InsufficientAllowance)
+        ));
+        assert_eq!(allowances.spend(&alice, &aurora, 60).unwrap(), 0);
+        assert!(!allowances
+            .allowances
+            .contains_key(&(alice.clone(), aurora.clone())));
+
+        // The allowance is directional
+        allowances.approve(&alice, &aurora, 100).unwrap();
+        assert_eq!(allowances.get(&aurora, &alice), 0);
+        allowances.approve(&alice, &aurora, 0).unwrap();
+        assert_eq!(allowances.get(&alice, &aurora), 0);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{connector_impl::FinishDepositCallArgs, Proof, WithdrawResult};
+use crate::{
+    connector_impl::{FinishDepositCallArgs, WithdrawBatchStatus},
+    Proof, WithdrawResult,
+};
 use aurora_engine_types::types::{Address, NEP141Wei};
 use near_sdk::{
     borsh, ext_contract,
@@ -9,6 +12,10 @@ use near_sdk::{
 #[ext_contract(ext_deposit)]
 pub trait ConnectorDeposit {
     fn deposit(&mut self, #[serializer(borsh)] raw_proof: Proof) -> Promise;
+
+    /// Same as `deposit`, but the proof is passed as JSON. Byte fields can be
+    /// `0x`-hex strings, base64 strings or arrays of numbers.
+    fn deposit_json(&mut self, proof: Proof) -> Promise;
 }
 
 #[ext_contract(ext_withdraw)]
@@ -19,7 +26,37 @@ pub trait ConnectorWithdraw {
         #[serializer(borsh)] sender_id: AccountId,
         #[serializer(borsh)] recipient_address: Address,
         #[serializer(borsh)] amount: NEP141Wei,
-    ) -> WithdrawResult;
+    ) -> PromiseOrValue<WithdrawResult>;
+
+    /// Withdraw tokens of several senders at once. The withdrawals are applied
+    /// atomically unless `isolate_failures` is set, in which case the entries
+    /// that would fail are skipped. Returns the status of each entry in order.
+    #[result_serializer(borsh)]
+    fn withdraw_batch(
+        &mut self,
+        #[serializer(borsh)] withdrawals: Vec<(AccountId, Address, NEP141Wei)>,
+        #[serializer(borsh)] isolate_failures: bool,
+    ) -> Vec<WithdrawBatchStatus>;
+
+    /// Withdraw tokens of the predecessor account to the Ethereum recipient.
+    /// Requires exactly 1 yoctoNEAR attached.
+    #[result_serializer(borsh)]
+    fn withdraw_to_eth(
+        &mut self,
+        #[serializer(borsh)] recipient_address: Address,
+        #[serializer(borsh)] amount: NEP141Wei,
+    ) -> PromiseOrValue<WithdrawResult>;
+
+    /// Withdraw tokens of the predecessor account to the Ethereum recipient and
+    /// notify `receiver_id` with `on_withdraw`. Requires exactly 1 yoctoNEAR attached.
+    /// Withdrawals above the delay threshold are refused.
+    fn withdraw_call(
+        &mut self,
+        #[serializer(borsh)] receiver_id: AccountId,
+        #[serializer(borsh)] recipient_address: Address,
+        #[serializer(borsh)] amount: NEP141Wei,
+        #[serializer(borsh)] msg: String,
+    ) -> Promise;
 }
 
 #[ext_contract(ext_funds_finish)]
@@ -31,6 +68,15 @@ pub trait ConnectorFundsFinish {
         #[serializer(borsh)]
         verify_log_result: bool,
     ) -> PromiseOrValue<Option<U128>>;
+
+    /// Credit the amount refunded by the engine after the failed EVM call of the
+    /// deposit to the plain address with the `msg` without the EVM call.
+    fn finish_deposit_call_fallback(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
 }
 
 #[ext_contract(ext_proof_verifier)]
@@ -0,0 +1,83 @@
+//! Events of the connector specific actions.
+//!
+//! The events follow the events format (nep-297) with the `eth_connector` standard:
+//! <https://github.com/near/NEPs/blob/master/specs/Standards/EventsFormat.md>
+
+use crate::fungible_token::event::NearEvent;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::AccountId;
+use serde::Serialize;
+
+/// Data to log for a finished `withdraw_call`. To log this event,
+/// call [`.emit()`](WithdrawCall::emit).
+#[must_use]
+#[derive(Serialize, Debug, Clone)]
+pub struct WithdrawCall<'a> {
+    pub sender_id: &'a AccountId,
+    pub receiver_id: &'a AccountId,
+    pub recipient_address: &'a str,
+    pub amount: &'a U128,
+    pub fee: &'a U128,
+    pub nonce: &'a U64,
+    /// Whether the receiver `on_withdraw` call succeeded
+    pub notified: bool,
+}
+
+impl WithdrawCall<'_> {
+    /// Logs the event to the host. This is required to ensure that the event is triggered
+    /// and to consume the event.
+    pub fn emit(self) {
+        Self::emit_many(&[self])
+    }
+
+    /// Emits a withdraw call event, through [`env::log_str`](near_sdk::env::log_str),
+    /// where each [`WithdrawCall`] represents the data of each withdrawal.
+    pub fn emit_many(data: &[WithdrawCall<'_>]) {
+        new_eth_connector_v1(EthConnectorEventKind::WithdrawCall(data)).emit()
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct EthConnectorEvent<'a> {
+    version: &'static str,
+    #[serde(flatten)]
+    event_kind: EthConnectorEventKind<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+enum EthConnectorEventKind<'a> {
+    WithdrawCall(&'a [WithdrawCall<'a>]),
+}
+
+fn new_eth_connector_v1(event_kind: EthConnectorEventKind) -> NearEvent {
+    NearEvent::EthConnector(EthConnectorEvent {
+        version: "1.0.0",
+        event_kind,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils;
+
+    #[test]
+    fn withdraw_call() {
+        WithdrawCall {
+            sender_id: &AccountId::new_unchecked("alice".to_string()),
+            receiver_id: &AccountId::new_unchecked("bob".to_string()),
+            recipient_address: "891b2749238b27ff58e951088e55b04de71dc374",
+            amount: &U128(90),
+            fee: &U128(10),
+            nonce: &U64(7),
+            notified: true,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"eth_connector","version":"1.0.0","event":"withdraw_call","data":[{"sender_id":"alice","receiver_id":"bob","recipient_address":"891b2749238b27ff58e951088e55b04de71dc374","amount":"90","fee":"10","nonce":"7","notified":true}]}"#
+        );
+    }
+}
@@ -3,19 +3,32 @@ use crate::{
     admin_controlled::PAUSE_DEPOSIT,
     connector::{ext_funds_finish, ext_proof_verifier, ConnectorDeposit},
     deposit_event::{DepositedEvent, TokenMessageData},
-    errors, log, panic_err,
+    engine_registry::EngineRegistry,
+    errors,
+    fee_policy::{DepositFee, FeePolicy, ProtocolFee},
+    log,
+    mint_limit::MintLimiter,
     proof::Proof,
     types::SdkUnwrap,
+    withdraw_fee::WithdrawFeeSchedule,
     AdminControlled, PausedMask,
 };
 use aurora_engine_types::types::{Address, Fee, NEP141Wei};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
-    env, AccountId, Gas, Promise,
+    env,
+    json_types::{Base64VecU8, U128},
+    serde::Serialize,
+    AccountId, Gas, Promise,
 };
 
 /// NEAR Gas for calling `fininsh_deposit` promise. Used in the `deposit` logic.
 pub const GAS_FOR_FINISH_DEPOSIT: Gas = Gas(50_000_000_000_000);
+/// NEAR Gas for calling `finish_deposit` of the deposit with the call. It covers
+/// the call of the receiver and the fallback of the EVM call to the plain address.
+pub const GAS_FOR_FINISH_DEPOSIT_CALL: Gas = Gas(200_000_000_000_000);
+/// NEAR Gas for calling `finish_deposit_call_fallback`.
+pub const GAS_FOR_DEPOSIT_CALL_FALLBACK: Gas = Gas(60_000_000_000_000);
 /// NEAR Gas for calling `verify_log_entry` promise. Used in the `deposit` logic.
 // Note: Is 40Tgas always enough?
 const GAS_FOR_VERIFY_LOG_ENTRY: Gas = Gas(40_000_000_000_000);
@@ -38,6 +51,109 @@ pub struct FinishDepositCallArgs {
     pub relayer_id: AccountId,
     pub fee: Fee,
     pub msg: Option<Vec<u8>>,
+    pub protocol_fee: Option<ProtocolFee>,
+    /// Ethereum sender of the deposit
+    pub sender: Address,
+    /// Transfer call of the minted tokens made on behalf of the owner
+    pub near_call: Option<NearDepositCall>,
+}
+
+/// `ft_transfer_call` of the deposited tokens to the receiver. The unused tokens
+/// are refunded to the deposit owner.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NearDepositCall {
+    pub receiver_id: AccountId,
+    pub msg: String,
+}
+
+impl FinishDepositCallArgs {
+    /// Total amount minted by the deposit, including the protocol fee.
+    pub fn mint_amount(&self) -> NEP141Wei {
+        match (&self.msg, &self.protocol_fee) {
+            // The protocol fee is already subtracted from the amount for Eth deposits
+            (Some(_), Some(protocol_fee)) => {
+                self.amount + NEP141Wei::new(protocol_fee.amount.as_u128())
+            }
+            _ => self.amount,
+        }
+    }
+}
+
+/// JSON view of `FinishDepositCallArgs`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FinishDepositView {
+    pub new_owner_id: AccountId,
+    pub amount: U128,
+    pub proof_key: String,
+    pub relayer_id: AccountId,
+    pub fee: U128,
+    pub msg: Option<Base64VecU8>,
+    pub protocol_fee: Option<U128>,
+    pub treasury_id: Option<AccountId>,
+    pub sender: String,
+    pub near_call: Option<NearDepositCall>,
+}
+
+impl From<&FinishDepositCallArgs> for FinishDepositView {
+    fn from(args: &FinishDepositCallArgs) -> Self {
+        Self {
+            new_owner_id: args.new_owner_id.clone(),
+            amount: args.amount.as_u128().into(),
+            proof_key: args.proof_key.clone(),
+            relayer_id: args.relayer_id.clone(),
+            fee: args.fee.as_u128().into(),
+            msg: args.msg.clone().map(Into::into),
+            protocol_fee: args
+                .protocol_fee
+                .as_ref()
+                .map(|protocol_fee| protocol_fee.amount.as_u128().into()),
+            treasury_id: args
+                .protocol_fee
+                .as_ref()
+                .map(|protocol_fee| protocol_fee.treasury_id.clone()),
+            sender: args.sender.encode(),
+            near_call: args.near_call.clone(),
+        }
+    }
+}
+
+/// Kind of the deposit recipient parsed from the event message
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenMessageKind {
+    Near,
+    NearCall,
+    Eth,
+}
+
+impl From<&TokenMessageData> for TokenMessageKind {
+    fn from(data: &TokenMessageData) -> Self {
+        match data {
+            TokenMessageData::Near(_) => Self::Near,
+            TokenMessageData::NearCall { .. } => Self::NearCall,
+            TokenMessageData::Eth { .. } => Self::Eth,
+        }
+    }
+}
+
+/// Report of a deposit dry-run. Fields that could not be evaluated before the
+/// first failed check are `None`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositSimulation {
+    pub proof_key: String,
+    pub sender: Option<String>,
+    pub kind: Option<TokenMessageKind>,
+    pub recipient: Option<AccountId>,
+    pub amount: Option<U128>,
+    pub fee: Option<U128>,
+    /// Arbitrary message of the `DepositedV2` event
+    pub message: Option<String>,
+    pub finish_deposit_args: Option<FinishDepositView>,
+    pub error: Option<String>,
 }
 
 /// withdraw result for eth-connector
@@ -46,6 +162,22 @@ pub struct WithdrawResult {
     pub amount: NEP141Wei,
     pub recipient_id: Address,
     pub eth_custodian_address: Address,
+    /// Nonce of the withdrawal in the withdrawal ledger
+    pub nonce: u64,
+    /// Withdraw fee credited to the fee collector. `amount` is net of the fee.
+    pub fee: NEP141Wei,
+}
+
+/// Status of a `withdraw_batch` entry. The batch returns one status for each entry,
+/// in the order of the entries.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum WithdrawBatchStatus {
+    /// The tokens are burned
+    Burned(WithdrawResult),
+    /// The withdrawal is delayed, `finalize_withdraw` returns its result
+    Queued { nonce: u64, unlock_block: u64 },
+    /// The entry is skipped with the error, only if the failures are isolated
+    Failed { error: String },
 }
 
 /// Connector specific data. It always should contain `prover account` -
@@ -61,6 +193,18 @@ pub struct EthConnector {
 
     /// Account with access right for current contract
     pub account_with_access_right: AccountId,
+
+    /// Relayer fee rules for deposits
+    pub fee_policy: FeePolicy,
+
+    /// Limits of the minted amount
+    pub mint_limiter: MintLimiter,
+
+    /// Fee charged on withdrawals
+    pub withdraw_fee: WithdrawFeeSchedule,
+
+    /// Engines served by the connector
+    pub engines: EngineRegistry,
 }
 
 impl AdminControlled for EthConnector {
@@ -81,6 +225,172 @@ impl AdminControlled for EthConnector {
     }
 }
 
+impl EthConnector {
+    /// Checks that the deposit event was emitted by the known custodian and that
+    /// the deposited amount covers the fee.
+    pub fn check_deposit_event(&self, event: &DepositedEvent) -> Result<(), FtDepositError> {
+        if event.eth_custodian_address != self.eth_custodian_address {
+            return Err(FtDepositError::CustodianAddressMismatch);
+        }
+        if NEP141Wei::new(event.fee.as_u128()) >= event.amount {
+            return Err(FtDepositError::InsufficientAmountForFee);
+        }
+        // Deposits to EVM accounts can only be sent to the served engines
+        if let TokenMessageData::Eth { receiver_id, .. } = &event.token_message_data {
+            if receiver_id == &env::current_account_id() {
+                return Ok(());
+            }
+            self.engines
+                .assert_deposit_target(receiver_id, &self.account_with_access_right, PAUSE_DEPOSIT)
+                .map_err(FtDepositError::InvalidEngine)?;
+        }
+        Ok(())
+    }
+
+    /// Prepare `finish_deposit` call args from the deposit event and the fee
+    /// calculated with the fee policy.
+    pub fn finish_deposit_args(
+        event: DepositedEvent,
+        fee: DepositFee,
+        proof_key: String,
+        relayer_id: AccountId,
+    ) -> FinishDepositCallArgs {
+        match event.token_message_data {
+            // Deposit to NEAR accounts
+            TokenMessageData::Near(account_id) => FinishDepositCallArgs {
+                new_owner_id: account_id,
+                amount: event.amount,
+                proof_key,
+                relayer_id,
+                fee: fee.relayer_fee,
+                msg: None,
+                protocol_fee: fee.protocol_fee,
+                sender: event.sender,
+                near_call: None,
+            },
+            // Deposit to NEAR accounts followed by the transfer call
+            TokenMessageData::NearCall {
+                account_id,
+                receiver_id,
+                msg,
+            } => FinishDepositCallArgs {
+                new_owner_id: account_id,
+                amount: event.amount,
+                proof_key,
+                relayer_id,
+                fee: fee.relayer_fee,
+                msg: None,
+                protocol_fee: fee.protocol_fee,
+                sender: event.sender,
+                near_call: Some(NearDepositCall { receiver_id, msg }),
+            },
+            // Deposit to Eth accounts
+            // fee is being minted in the `ft_on_transfer` callback method
+            TokenMessageData::Eth {
+                receiver_id,
+                mut message,
+            } => {
+                // The protocol fee share is minted to the treasury,
+                // the rest of the amount is transferred to the receiver.
+                let protocol_fee = fee
+                    .protocol_fee
+                    .as_ref()
+                    .map_or(0, |protocol_fee| protocol_fee.amount.as_u128());
+                let amount = event.amount - NEP141Wei::new(protocol_fee);
+                message.fee = fee.relayer_fee;
+
+                // Transfer to self and then transfer ETH in `ft_on_transfer`
+                // address - is NEAR account
+                let transfer_data = TransferCallCallArgs {
+                    receiver_id,
+                    amount,
+                    memo: None,
+                    msg: message.encode(),
+                }
+                .try_to_vec()
+                .map_err(|_| errors::ERR_BORSH_SERIALIZE)
+                .sdk_unwrap();
+
+                // Send to self - current account id
+                FinishDepositCallArgs {
+                    new_owner_id: env::current_account_id(),
+                    amount,
+                    proof_key,
+                    relayer_id,
+                    fee: fee.relayer_fee,
+                    msg: Some(transfer_data),
+                    protocol_fee: fee.protocol_fee,
+                    sender: event.sender,
+                    near_call: None,
+                }
+            }
+        }
+    }
+
+    /// Run the deposit checks without calling the prover.
+    /// `is_used_proof` reports whether the proof key has already been used.
+    pub fn simulate_deposit<F>(
+        &self,
+        proof: &Proof,
+        relayer_id: AccountId,
+        is_used_proof: F,
+    ) -> DepositSimulation
+    where
+        F: Fn(&str) -> bool,
+    {
+        let proof_key = proof.get_key();
+        let mut simulation = DepositSimulation {
+            proof_key: proof_key.clone(),
+            sender: None,
+            kind: None,
+            recipient: None,
+            amount: None,
+            fee: None,
+            message: None,
+            finish_deposit_args: None,
+            error: None,
+        };
+
+        let event = match DepositedEvent::from_log_entry_data(&proof.log_entry_data) {
+            Ok(event) => event,
+            Err(e) => {
+                simulation.error = Some(error_to_string(e));
+                return simulation;
+            }
+        };
+        simulation.sender = Some(event.sender.encode());
+        simulation.kind = Some(TokenMessageKind::from(&event.token_message_data));
+        simulation.recipient = Some(event.token_message_data.get_recipient());
+        simulation.amount = Some(event.amount.as_u128().into());
+        simulation.fee = Some(event.fee.as_u128().into());
+        simulation.message = event.message.clone();
+
+        if let Err(e) = self.check_deposit_event(&event) {
+            simulation.error = Some(error_to_string(e));
+            return simulation;
+        }
+        let fee = match self.fee_policy.apply(event.amount, event.fee) {
+            Ok(fee) => fee,
+            Err(e) => {
+                simulation.error = Some(error_to_string(e));
+                return simulation;
+            }
+        };
+        if is_used_proof(&proof_key) {
+            simulation.error = Some(error_to_string(errors::ERR_PROOF_EXIST));
+            return simulation;
+        }
+
+        let args = Self::finish_deposit_args(event, fee, proof_key, relayer_id);
+        simulation.finish_deposit_args = Some((&args).into());
+        simulation
+    }
+}
+
+fn error_to_string<E: AsRef<[u8]>>(e: E) -> String {
+    String::from_utf8_lossy(e.as_ref()).into_owned()
+}
+
 impl ConnectorDeposit for EthConnector {
     fn deposit(&mut self, raw_proof: Proof) -> Promise {
         let current_account_id = env::current_account_id();
@@ -97,11 +407,12 @@ impl ConnectorDeposit for EthConnector {
         let event = DepositedEvent::from_log_entry_data(&proof.log_entry_data).sdk_unwrap();
 
         log!(
-            "Deposit started: from {} to recipient {:?} with amount: {:?} and fee {:?}",
+            "Deposit started: from {} to recipient {:?} with amount: {:?}, fee {:?} and message {:?}",
             event.sender.encode(),
             event.token_message_data.get_recipient(),
             event.amount,
-            event.fee
+            event.fee,
+            event.message
         );
 
         log!(
@@ -110,13 +421,9 @@ impl ConnectorDeposit for EthConnector {
             self.eth_custodian_address.encode(),
         );
 
-        if event.eth_custodian_address != self.eth_custodian_address {
-            panic_err(FtDepositError::CustodianAddressMismatch);
-        }
-
-        if NEP141Wei::new(event.fee.as_u128()) >= event.amount {
-            panic_err(FtDepositError::InsufficientAmountForFee);
-        }
+        self.check_deposit_event(&event).sdk_unwrap();
+        // Apply the relayer fee policy
+        let fee = self.fee_policy.apply(event.amount, event.fee).sdk_unwrap();
 
         // Verify proof data with cross-contract call to prover account
         log!(
@@ -129,54 +436,30 @@ impl ConnectorDeposit for EthConnector {
         let mut proof_to_verify = raw_proof.try_to_vec().unwrap();
         proof_to_verify.extend(skip_bridge_call);
 
-        // Finalize deposit
-        let finish_deposit_data = match event.token_message_data {
-            // Deposit to NEAR accounts
-            TokenMessageData::Near(account_id) => FinishDepositCallArgs {
-                new_owner_id: account_id,
-                amount: event.amount,
-                proof_key: proof.get_key(),
-                relayer_id: predecessor_account_id,
-                fee: event.fee,
-                msg: None,
-            },
-            // Deposit to Eth accounts
-            // fee is being minted in the `ft_on_transfer` callback method
-            TokenMessageData::Eth {
-                receiver_id,
-                message,
-            } => {
-                // Transfer to self and then transfer ETH in `ft_on_transfer`
-                // address - is NEAR account
-                let transfer_data = TransferCallCallArgs {
-                    receiver_id,
-                    amount: event.amount,
-                    memo: None,
-                    msg: message.encode(),
-                }
-                .try_to_vec()
-                .map_err(|_| errors::ERR_BORSH_SERIALIZE)
-                .sdk_unwrap();
-
-                // Send to self - current account id
-                FinishDepositCallArgs {
-                    new_owner_id: current_account_id.clone(),
-                    amount: event.amount,
-                    proof_key: proof.get_key(),
-                    relayer_id: predecessor_account_id,
-                    fee: event.fee,
-                    msg: Some(transfer_data),
-                }
+        // Deposits with the call need the gas for the call of the receiver
+        let finish_deposit_gas = match &event.token_message_data {
+            TokenMessageData::Eth { message, .. } if message.evm_call.is_some() => {
+                GAS_FOR_FINISH_DEPOSIT_CALL
             }
+            TokenMessageData::NearCall { .. } => GAS_FOR_FINISH_DEPOSIT_CALL,
+            _ => GAS_FOR_FINISH_DEPOSIT,
         };
 
+        // Finalize deposit
+        let finish_deposit_data =
+            Self::finish_deposit_args(event, fee, proof.get_key(), predecessor_account_id);
+
         ext_proof_verifier::ext(self.prover_account.clone())
             .with_static_gas(GAS_FOR_VERIFY_LOG_ENTRY)
             .verify_log_entry(proof_to_verify.into())
             .then(
                 ext_funds_finish::ext(current_account_id)
-                    .with_static_gas(GAS_FOR_FINISH_DEPOSIT)
+                    .with_static_gas(finish_deposit_gas)
                     .finish_deposit(finish_deposit_data),
             )
     }
+
+    fn deposit_json(&mut self, proof: Proof) -> Promise {
+        self.deposit(proof)
+    }
 }
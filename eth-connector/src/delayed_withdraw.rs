@@ -0,0 +1,193 @@
+use aurora_engine_types::types::{Address, NEP141Wei};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    AccountId, BlockHeight, Gas, IntoStorageKey,
+};
+
+pub const GAS_FOR_WITHDRAW_QUEUED: Gas = Gas(5_000_000_000_000);
+
+/// Withdrawal delay settings. Withdrawals are not delayed by default.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawDelayConfig {
+    /// Withdrawals of a bigger amount are delayed.
+    pub threshold: Option<U128>,
+    /// Delay in blocks.
+    pub delay_blocks: U64,
+    /// Account allowed to cancel queued withdrawals, in addition to the owner.
+    pub guardian_id: Option<AccountId>,
+}
+
+impl Default for WithdrawDelayConfig {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            delay_blocks: 0.into(),
+            guardian_id: None,
+        }
+    }
+}
+
+impl WithdrawDelayConfig {
+    pub fn is_delayed(&self, amount: NEP141Wei) -> bool {
+        matches!(self.threshold, Some(threshold) if amount.as_u128() > threshold.0)
+    }
+}
+
+/// Withdrawal waiting for the delay. The tokens are locked in the contract state.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct QueuedWithdrawal {
+    pub nonce: u64,
+    pub sender_id: AccountId,
+    pub recipient_address: Address,
+    pub amount: NEP141Wei,
+    /// Withdraw fee fixed when the withdrawal is queued.
+    pub fee: NEP141Wei,
+    /// Engine which made the withdrawal. Its supply is restored if the withdrawal is cancelled.
+    pub engine_id: Option<AccountId>,
+    /// Block height since which the withdrawal can be finalized.
+    pub unlock_block: BlockHeight,
+}
+
+/// Outcome of the withdrawal. Only the burned withdrawal has the `WithdrawResult`
+/// for the Ethereum custodian, the queued one is burned by `finalize_withdraw`.
+pub enum WithdrawOutcome {
+    Burned(crate::WithdrawResult),
+    Queued {
+        nonce: u64,
+        unlock_block: BlockHeight,
+    },
+}
+
+/// JSON view of `QueuedWithdrawal`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QueuedWithdrawalView {
+    pub nonce: U64,
+    pub sender_id: AccountId,
+    pub recipient_address: String,
+    pub amount: U128,
+    pub fee: U128,
+    pub engine_id: Option<AccountId>,
+    pub unlock_block: U64,
+}
+
+impl From<QueuedWithdrawal> for QueuedWithdrawalView {
+    fn from(withdrawal: QueuedWithdrawal) -> Self {
+        Self {
+            nonce: withdrawal.nonce.into(),
+            sender_id: withdrawal.sender_id,
+            recipient_address: withdrawal.recipient_address.encode(),
+            amount: withdrawal.amount.as_u128().into(),
+            fee: withdrawal.fee.as_u128().into(),
+            engine_id: withdrawal.engine_id,
+            unlock_block: withdrawal.unlock_block.into(),
+        }
+    }
+}
+
+/// Queue of delayed withdrawals by nonce.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct WithdrawalQueue {
+    pub config: WithdrawDelayConfig,
+    pub withdrawals: LookupMap<u64, QueuedWithdrawal>,
+}
+
+impl WithdrawalQueue {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            config: WithdrawDelayConfig::default(),
+            withdrawals: LookupMap::new(prefix),
+        }
+    }
+
+    pub fn push(&mut self, withdrawal: &QueuedWithdrawal) {
+        self.withdrawals.insert(&withdrawal.nonce, withdrawal);
+    }
+
+    pub fn get(&self, nonce: u64) -> Option<QueuedWithdrawal> {
+        self.withdrawals.get(&nonce)
+    }
+
+    /// Remove the withdrawal which delay has passed.
+    pub fn take_unlocked(
+        &mut self,
+        nonce: u64,
+        block_height: BlockHeight,
+    ) -> Result<QueuedWithdrawal, error::DelayedWithdrawError> {
+        let withdrawal = self
+            .get(nonce)
+            .ok_or(error::DelayedWithdrawError::NotQueued)?;
+        if block_height < withdrawal.unlock_block {
+            return Err(error::DelayedWithdrawError::TooEarly);
+        }
+        self.withdrawals.remove(&nonce);
+        Ok(withdrawal)
+    }
+
+    pub fn remove(&mut self, nonce: u64) -> Result<QueuedWithdrawal, error::DelayedWithdrawError> {
+        self.withdrawals
+            .remove(&nonce)
+            .ok_or(error::DelayedWithdrawError::NotQueued)
+    }
+
+    /// Check if the account is allowed to cancel withdrawals.
+    pub fn assert_guardian(
+        &self,
+        account_id: &AccountId,
+        is_owner: bool,
+    ) -> Result<(), error::DelayedWithdrawError> {
+        if is_owner || self.config.guardian_id.as_ref() == Some(account_id) {
+            Ok(())
+        } else {
+            Err(error::DelayedWithdrawError::NotGuardian)
+        }
+    }
+}
+
+#[ext_contract(ext_delayed_withdraw)]
+pub trait DelayedWithdraw {
+    /// Burn the tokens of the queued withdrawal after the delay.
+    /// Returns the `WithdrawResult` for the Ethereum custodian.
+    #[result_serializer(borsh)]
+    fn finalize_withdraw(&mut self, nonce: U64) -> crate::WithdrawResult;
+
+    /// Cancel the queued withdrawal and refund the sender. Allowed for the guardian and the owner.
+    fn cancel_withdraw(&mut self, nonce: U64);
+
+    /// Callback of the queued `withdraw` and `withdraw_to_eth`. Returns nothing,
+    /// so the outcome of the queued withdrawal can't be used as a withdrawal proof.
+    fn withdraw_queued(&self, nonce: U64);
+
+    fn get_queued_withdrawal(&self, nonce: U64) -> Option<QueuedWithdrawalView>;
+
+    /// Set the withdrawal delay settings.
+    fn set_withdraw_delay_config(&mut self, config: WithdrawDelayConfig);
+
+    fn get_withdraw_delay_config(&self) -> WithdrawDelayConfig;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum DelayedWithdrawError {
+        NotQueued,
+        TooEarly,
+        NotGuardian,
+    }
+
+    impl AsRef<[u8]> for DelayedWithdrawError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::NotQueued => errors::ERR_WITHDRAW_NOT_QUEUED,
+                Self::TooEarly => errors::ERR_NOT_ALLOWED_TOO_EARLY,
+                Self::NotGuardian => errors::ERR_NOT_GUARDIAN,
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::connector_impl::FinishDepositCallArgs;
+use crate::pending_deposit::HoldReason;
+use aurora_engine_types::types::Address;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupSet,
+    ext_contract, AccountId, IntoStorageKey,
+};
+
+/// Accounts and Ethereum senders whose deposits are held instead of minted.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Denylist {
+    pub accounts: LookupSet<AccountId>,
+    pub suspicious_addresses: LookupSet<Address>,
+}
+
+impl Denylist {
+    pub fn new<S: IntoStorageKey>(accounts_prefix: S, addresses_prefix: S) -> Self {
+        Self {
+            accounts: LookupSet::new(accounts_prefix),
+            suspicious_addresses: LookupSet::new(addresses_prefix),
+        }
+    }
+
+    /// Return the reason to hold the deposit. The recipient and the receiver of the
+    /// transfer call are checked against the denied accounts, the Ethereum sender
+    /// against the suspicious addresses.
+    pub fn hold_reason(&self, deposit_call: &FinishDepositCallArgs) -> Option<HoldReason> {
+        let is_receiver_denied = matches!(
+            &deposit_call.near_call,
+            Some(near_call) if self.accounts.contains(&near_call.receiver_id)
+        );
+        if self.accounts.contains(&deposit_call.new_owner_id) || is_receiver_denied {
+            Some(HoldReason::RecipientDenied)
+        } else if self.suspicious_addresses.contains(&deposit_call.sender) {
+            Some(HoldReason::Suspicious)
+        } else {
+            None
+        }
+    }
+}
+
+#[ext_contract(ext_denylist)]
+pub trait DenylistManagement {
+    /// Hold the deposits to the account.
+    fn add_denied_account(&mut self, account_id: AccountId);
+
+    fn remove_denied_account(&mut self, account_id: AccountId);
+
+    fn is_account_denied(&self, account_id: AccountId) -> bool;
+
+    /// Hold the deposits sent from the Ethereum address as suspicious.
+    fn add_suspicious_address(&mut self, address: String);
+
+    fn remove_suspicious_address(&mut self, address: String);
+
+    fn is_address_suspicious(&self, address: String) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector_impl::NearDepositCall;
+    use aurora_engine_types::types::{Fee, NEP141Wei};
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn deposit_call(near_call: Option<NearDepositCall>) -> FinishDepositCallArgs {
+        FinishDepositCallArgs {
+            new_owner_id: "owner".parse().unwrap(),
+            amount: NEP141Wei::new(100),
+            proof_key: "proof".to_string(),
+            relayer_id: "relayer".parse().unwrap(),
+            fee: Fee::new(NEP141Wei::new(0)),
+            msg: None,
+            protocol_fee: None,
+            sender: Address::zero(),
+            near_call,
+        }
+    }
+
+    #[test]
+    fn test_hold_reason() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut denylist = Denylist::new(b"a".to_vec(), b"s".to_vec());
+        let near_call = NearDepositCall {
+            receiver_id: "receiver".parse().unwrap(),
+            msg: String::new(),
+        };
+        assert_eq!(denylist.hold_reason(&deposit_call(None)), None);
+
+        denylist.suspicious_addresses.insert(&Address::zero());
+        assert_eq!(
+            denylist.hold_reason(&deposit_call(None)),
+            Some(HoldReason::Suspicious)
+        );
+
+        denylist.accounts.insert(&near_call.receiver_id);
+        assert_eq!(
+            denylist.hold_reason(&deposit_call(Some(near_call))),
+            Some(HoldReason::RecipientDenied)
+        );
+        denylist.accounts.insert(&"owner".parse().unwrap());
+        assert_eq!(
+            denylist.hold_reason(&deposit_call(None)),
+            Some(HoldReason::RecipientDenied)
+        );
+    }
+}
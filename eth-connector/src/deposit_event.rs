@@ -7,13 +7,27 @@ use byte_slice_cast::AsByteSlice;
 use ethabi::{Event, EventParam, Hash, Log, ParamType, RawLog};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U128,
+    serde::{Deserialize, Serialize},
     AccountId,
 };
 
 pub const DEPOSITED_EVENT: &str = "Deposited";
+pub const DEPOSITED_V2_EVENT: &str = "DepositedV2";
+
+/// Current version of the JSON on-transfer message envelope.
+pub const FT_TRANSFER_MESSAGE_VERSION: u8 = 1;
 
 pub type EventParams = Vec<EventParam>;
 
+/// EVM contract call made by the engine after the tokens are credited.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct EvmCallData {
+    pub contract: Address,
+    pub input: Vec<u8>,
+}
+
 /// On-transfer message. Used for `ft_transfer_call` and  `ft_on_transfer` functions.
 /// Message parsed from input args with `parse_on_transfer_message`.
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -22,14 +36,52 @@ pub struct FtTransferMessageData {
     pub relayer: AccountId,
     pub recipient: Address,
     pub fee: Fee,
+    /// Only carried by the versioned message format
+    pub evm_call: Option<EvmCallData>,
+}
+
+/// Versioned JSON envelope of the on-transfer message.
+/// Addresses and the call input are hex strings with an optional `0x` prefix.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct FtTransferMessageEnvelope {
+    version: u8,
+    relayer: AccountId,
+    fee: U128,
+    recipient: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    evm_call: Option<EvmCallEnvelope>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EvmCallEnvelope {
+    contract: String,
+    input: String,
+}
+
+fn decode_hex(data: &str) -> Result<Vec<u8>, error::ParseOnTransferMessageError> {
+    hex::decode(data.strip_prefix("0x").unwrap_or(data))
+        .map_err(|_| error::ParseOnTransferMessageError::InvalidHexData)
+}
+
+fn decode_address(data: &str) -> Result<Address, error::ParseOnTransferMessageError> {
+    Address::try_from_slice(&decode_hex(data)?)
+        .map_err(|_| error::ParseOnTransferMessageError::WrongMessageFormat)
 }
 
 impl FtTransferMessageData {
     /// Get on-transfer data from arguments message field.
-    /// Used for `ft_transfer_call` and `ft_on_transfer`
+    /// Used for `ft_transfer_call` and `ft_on_transfer`.
+    /// A message starting with `{` is parsed as the versioned JSON envelope,
+    /// otherwise as the legacy `relayer:hex(fee ‖ address)` format.
     pub fn parse_on_transfer_message(
         message: &str,
     ) -> Result<Self, error::ParseOnTransferMessageError> {
+        if message.starts_with('{') {
+            return Self::parse_versioned_message(message);
+        }
+
         // Split message by separator
         let data: Vec<_> = message.split(':').collect();
         // Message data array should contain 2 elements
@@ -65,11 +117,43 @@ impl FtTransferMessageData {
             relayer: account_id,
             recipient,
             fee,
+            evm_call: None,
+        })
+    }
+
+    fn parse_versioned_message(message: &str) -> Result<Self, error::ParseOnTransferMessageError> {
+        let envelope: FtTransferMessageEnvelope = near_sdk::serde_json::from_str(message)
+            .map_err(|_| error::ParseOnTransferMessageError::InvalidJson)?;
+        if envelope.version != FT_TRANSFER_MESSAGE_VERSION {
+            return Err(error::ParseOnTransferMessageError::UnsupportedVersion);
+        }
+        let evm_call = envelope
+            .evm_call
+            .map(
+                |evm_call| -> Result<_, error::ParseOnTransferMessageError> {
+                    Ok(EvmCallData {
+                        contract: decode_address(&evm_call.contract)?,
+                        input: decode_hex(&evm_call.input)?,
+                    })
+                },
+            )
+            .transpose()?;
+
+        Ok(Self {
+            relayer: envelope.relayer,
+            recipient: decode_address(&envelope.recipient)?,
+            fee: envelope.fee.0.into(),
+            evm_call,
         })
     }
 
-    /// Encode to String with specific rules
+    /// Encode to String with specific rules. The legacy format is used unless
+    /// the message carries data which only the versioned format supports.
     pub fn encode(&self) -> String {
+        if self.evm_call.is_some() {
+            return self.encode_versioned();
+        }
+
         // The first data section should contain fee data.
         // Pay attention, that for compatibility reasons we used U256 type
         // it means 32 bytes for fee data
@@ -80,6 +164,22 @@ impl FtTransferMessageData {
         [self.relayer.as_ref(), &hex::encode(data)].join(":")
     }
 
+    /// Encode to the versioned JSON envelope
+    pub fn encode_versioned(&self) -> String {
+        let envelope = FtTransferMessageEnvelope {
+            version: FT_TRANSFER_MESSAGE_VERSION,
+            relayer: self.relayer.clone(),
+            fee: self.fee.as_u128().into(),
+            recipient: self.recipient.encode(),
+            evm_call: self.evm_call.as_ref().map(|evm_call| EvmCallEnvelope {
+                contract: evm_call.contract.encode(),
+                input: hex::encode(&evm_call.input),
+            }),
+        };
+        // The envelope cannot fail to serialize
+        near_sdk::serde_json::to_string(&envelope).unwrap_or_default()
+    }
+
     /// Prepare message for `ft_transfer_call` -> `ft_on_transfer`
     pub fn prepare_message_for_on_transfer(
         relayer_account_id: &AccountId,
@@ -111,6 +211,7 @@ impl FtTransferMessageData {
             relayer: relayer_account_id.clone(),
             recipient: recipient_address,
             fee,
+            evm_call: None,
         })
     }
 }
@@ -124,6 +225,12 @@ impl FtTransferMessageData {
 pub enum TokenMessageData {
     /// Deposit no NEAR account
     Near(AccountId),
+    /// Deposit to NEAR account followed by `ft_transfer_call` of the tokens to `receiver_id`
+    NearCall {
+        account_id: AccountId,
+        receiver_id: AccountId,
+        msg: String,
+    },
     ///Deposit to Eth accounts fee is being minted in the `ft_on_transfer` callback method
     Eth {
         receiver_id: AccountId,
@@ -142,10 +249,14 @@ impl TokenMessageData {
         fee: Fee,
     ) -> Result<TokenMessageData, error::ParseEventMessageError> {
         let data: Vec<_> = message.split(':').collect();
-        // Data array can contain 1 or 2 elements
-        if data.len() >= 3 {
+        // Data array can contain 1, 2 or 4 elements
+        if data.len() > 4 {
             return Err(error::ParseEventMessageError::TooManyParts);
         }
+        // The EVM call needs both the contract and the call input
+        if data.len() == 3 {
+            return Err(error::ParseEventMessageError::TooFewParts);
+        }
         let account_id = AccountId::try_from(data[0].to_string())
             .map_err(|_| error::ParseEventMessageError::InvalidAccount)?;
 
@@ -154,11 +265,15 @@ impl TokenMessageData {
             Ok(TokenMessageData::Near(account_id))
         } else {
             let raw_message = data[1].into();
-            let message = FtTransferMessageData::prepare_message_for_on_transfer(
+            let mut message = FtTransferMessageData::prepare_message_for_on_transfer(
                 &account_id,
                 fee,
                 raw_message,
             )?;
+            // The last two elements are the EVM contract and the call input
+            if data.len() == 4 {
+                message.evm_call = Some(Self::parse_evm_call(data[2], data[3])?);
+            }
 
             Ok(TokenMessageData::Eth {
                 receiver_id: account_id,
@@ -167,10 +282,23 @@ impl TokenMessageData {
         }
     }
 
+    /// Parse the EVM call from the hex contract address and the hex call input.
+    pub fn parse_evm_call(
+        contract: &str,
+        input: &str,
+    ) -> Result<EvmCallData, error::ParseEventMessageError> {
+        Ok(EvmCallData {
+            contract: decode_address(contract)
+                .map_err(error::ParseEventMessageError::ParseMessageError)?,
+            input: decode_hex(input).map_err(error::ParseEventMessageError::ParseMessageError)?,
+        })
+    }
+
     // Get recipient account id from Eth part of Token message data
     pub fn get_recipient(&self) -> AccountId {
         match self {
             Self::Near(acc) => acc.clone(),
+            Self::NearCall { account_id, .. } => account_id.clone(),
             Self::Eth {
                 receiver_id,
                 message: _,
@@ -215,6 +343,16 @@ impl EthEvent {
             log,
         })
     }
+
+    /// Event signature hash, the first topic of the event log.
+    pub fn signature(name: &str, params: EventParams) -> Hash {
+        Event {
+            name: name.to_string(),
+            inputs: params,
+            anonymous: false,
+        }
+        .signature()
+    }
 }
 
 /// Data that was emitted by Deposited event.
@@ -224,6 +362,8 @@ pub struct DepositedEvent {
     pub token_message_data: TokenMessageData,
     pub amount: NEP141Wei,
     pub fee: Fee,
+    /// Arbitrary message of the `DepositedV2` event
+    pub message: Option<String>,
 }
 
 impl DepositedEvent {
@@ -253,8 +393,62 @@ impl DepositedEvent {
         ]
     }
 
-    /// Parses raw Ethereum logs proof's entry data
+    /// Params of the `DepositedV2` event. The EVM recipient is optional: the zero
+    /// address means the deposit to the NEAR account. `target` is the engine account
+    /// the EVM deposit is sent to, `recipient` is used if it's empty. The `message`
+    /// of the EVM deposit is an optional `contract:input` EVM call in hex.
+    /// The NEAR deposit with `target` is transferred to it with `ft_transfer_call`
+    /// and the `message`.
+    pub fn event_v2_params() -> EventParams {
+        vec![
+            EventParam {
+                name: "sender".to_string(),
+                kind: ParamType::Address,
+                indexed: true,
+            },
+            EventParam {
+                name: "recipient".to_string(),
+                kind: ParamType::String,
+                indexed: false,
+            },
+            EventParam {
+                name: "evm_recipient".to_string(),
+                kind: ParamType::Address,
+                indexed: false,
+            },
+            EventParam {
+                name: "amount".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "fee".to_string(),
+                kind: ParamType::Uint(256),
+                indexed: false,
+            },
+            EventParam {
+                name: "message".to_string(),
+                kind: ParamType::String,
+                indexed: false,
+            },
+            EventParam {
+                name: "target".to_string(),
+                kind: ParamType::String,
+                indexed: false,
+            },
+        ]
+    }
+
+    /// Parses raw Ethereum logs proof's entry data. The event version is detected
+    /// by the topic hash, `Deposited` is parsed otherwise.
     pub fn from_log_entry_data(data: &[u8]) -> Result<Self, error::ParseError> {
+        let log_entry: LogEntry = rlp::decode(data)
+            .map_err(|_| error::ParseError::LogParseFailed(error::DecodeError::RlpFailed))?;
+        let v2_signature = EthEvent::signature(DEPOSITED_V2_EVENT, Self::event_v2_params());
+        if matches!(log_entry.topics.first(), Some(topic) if topic.0 == v2_signature.0) {
+            return Self::from_v2_log_entry_data(data);
+        }
+
         let event = EthEvent::fetch_log_entry_data(DEPOSITED_EVENT, Self::event_params(), data)
             .map_err(error::ParseError::LogParseFailed)?;
         let raw_sender = event.log.params[0]
@@ -297,6 +491,96 @@ impl DepositedEvent {
             token_message_data,
             amount,
             fee,
+            message: None,
+        })
+    }
+
+    /// Parses `DepositedV2` event from raw Ethereum logs proof's entry data
+    fn from_v2_log_entry_data(data: &[u8]) -> Result<Self, error::ParseError> {
+        let event =
+            EthEvent::fetch_log_entry_data(DEPOSITED_V2_EVENT, Self::event_v2_params(), data)
+                .map_err(error::ParseError::LogParseFailed)?;
+        let mut params = event.log.params.into_iter().map(|param| param.value);
+        let mut next_param = || params.next().ok_or(error::ParseError::InvalidParams);
+
+        let sender = next_param()?
+            .into_address()
+            .map(|address| Address::from_array(address.0))
+            .ok_or(error::ParseError::InvalidSender)?;
+        let recipient = next_param()?
+            .into_string()
+            .ok_or(error::ParseEventMessageError::InvalidAccount)?;
+        let evm_recipient = next_param()?
+            .into_address()
+            .map(|address| Address::from_array(address.0))
+            .ok_or(error::ParseEventMessageError::EthAddressValidationError(
+                AddressError::IncorrectLength,
+            ))?;
+        let amount = next_param()?
+            .into_uint()
+            .ok_or(error::ParseError::InvalidAmount)?
+            .try_into()
+            .map(NEP141Wei::new)
+            .map_err(|_| error::ParseError::OverflowNumber)?;
+        let fee = next_param()?
+            .into_uint()
+            .ok_or(error::ParseError::InvalidFee)?
+            .try_into()
+            .map(|v| Fee::new(NEP141Wei::new(v)))
+            .map_err(|_| error::ParseError::OverflowNumber)?;
+        let message = next_param()?
+            .into_string()
+            .ok_or(error::ParseError::InvalidParams)?;
+        let target = next_param()?
+            .into_string()
+            .ok_or(error::ParseError::InvalidParams)?;
+
+        let account_id = AccountId::try_from(recipient)
+            .map_err(|_| error::ParseEventMessageError::InvalidAccount)?;
+        let token_message_data = if evm_recipient != Address::zero() {
+            let receiver_id = if target.is_empty() {
+                account_id
+            } else {
+                AccountId::try_from(target)
+                    .map_err(|_| error::ParseEventMessageError::InvalidAccount)?
+            };
+            // The message of the EVM deposit is arbitrary. The `contract:input` message
+            // with the hex contract address and call input is the EVM call.
+            let evm_call = message.split_once(':').and_then(|(contract, input)| {
+                TokenMessageData::parse_evm_call(contract, input).ok()
+            });
+            TokenMessageData::Eth {
+                message: FtTransferMessageData {
+                    relayer: receiver_id.clone(),
+                    recipient: evm_recipient,
+                    fee,
+                    evm_call,
+                },
+                receiver_id,
+            }
+        } else if target.is_empty() {
+            TokenMessageData::Near(account_id)
+        } else {
+            // The NEAR deposit with the target is transferred to the target with the message
+            let receiver_id = AccountId::try_from(target)
+                .map_err(|_| error::ParseEventMessageError::InvalidAccount)?;
+            if receiver_id == account_id {
+                return Err(error::ParseEventMessageError::InvalidAccount.into());
+            }
+            TokenMessageData::NearCall {
+                account_id,
+                receiver_id,
+                msg: message.clone(),
+            }
+        };
+
+        Ok(Self {
+            eth_custodian_address: event.eth_custodian_address,
+            sender,
+            token_message_data,
+            amount,
+            fee,
+            message: (!message.is_empty()).then_some(message),
         })
     }
 }
@@ -322,6 +606,7 @@ pub mod error {
     #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
     pub enum ParseEventMessageError {
         TooManyParts,
+        TooFewParts,
         InvalidAccount,
         EthAddressValidationError(AddressError),
         ParseMessageError(ParseOnTransferMessageError),
@@ -330,7 +615,7 @@ pub mod error {
     impl AsRef<[u8]> for ParseEventMessageError {
         fn as_ref(&self) -> &[u8] {
             match self {
-                Self::TooManyParts => errors::ERR_INVALID_EVENT_MESSAGE_FORMAT,
+                Self::TooManyParts | Self::TooFewParts => errors::ERR_INVALID_EVENT_MESSAGE_FORMAT,
                 Self::InvalidAccount => errors::ERR_INVALID_ACCOUNT_ID,
                 Self::EthAddressValidationError(e) => e.as_ref(),
                 Self::ParseMessageError(e) => e.as_ref(),
@@ -350,6 +635,7 @@ pub mod error {
         InvalidSender,
         InvalidAmount,
         InvalidFee,
+        InvalidParams,
         MessageParseFailed(ParseEventMessageError),
         OverflowNumber,
     }
@@ -360,6 +646,7 @@ pub mod error {
                 Self::InvalidSender => errors::ERR_INVALID_SENDER,
                 Self::InvalidAmount => errors::ERR_INVALID_AMOUNT,
                 Self::InvalidFee => errors::ERR_INVALID_FEE,
+                Self::InvalidParams => errors::ERR_PARSE_DEPOSIT_EVENT,
                 Self::MessageParseFailed(e) => e.as_ref(),
                 Self::OverflowNumber => errors::ERR_OVERFLOW_NUMBER,
             }
@@ -373,6 +660,8 @@ pub mod error {
         WrongMessageFormat,
         InvalidAccount,
         OverflowNumber,
+        InvalidJson,
+        UnsupportedVersion,
     }
 
     impl AsRef<[u8]> for ParseOnTransferMessageError {
@@ -383,7 +672,232 @@ pub mod error {
                 Self::WrongMessageFormat => errors::ERR_INVALID_ON_TRANSFER_MESSAGE_DATA,
                 Self::InvalidAccount => errors::ERR_INVALID_ACCOUNT_ID,
                 Self::OverflowNumber => errors::ERR_OVERFLOW_NUMBER,
+                Self::InvalidJson => errors::ERR_INVALID_ON_TRANSFER_MESSAGE_JSON,
+                Self::UnsupportedVersion => errors::ERR_UNSUPPORTED_ON_TRANSFER_MESSAGE_VERSION,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aurora_engine_types::H256;
+    use ethabi::Token;
+
+    fn log_entry_data(name: &str, params: EventParams, data: &[Token]) -> Vec<u8> {
+        let log_entry = LogEntry {
+            address: Address::from_array([1u8; 20]).raw(),
+            topics: vec![H256(EthEvent::signature(name, params).0), H256::zero()],
+            data: ethabi::encode(data),
+        };
+        rlp::encode(&log_entry).to_vec()
+    }
+
+    fn v2_log_entry_data(evm_recipient: [u8; 20], message: &str, target: &str) -> Vec<u8> {
+        log_entry_data(
+            DEPOSITED_V2_EVENT,
+            DepositedEvent::event_v2_params(),
+            &[
+                Token::String("alice.near".to_string()),
+                Token::Address(evm_recipient.into()),
+                Token::Uint(1000.into()),
+                Token::Uint(10.into()),
+                Token::String(message.to_string()),
+                Token::String(target.to_string()),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_parse_legacy_event() {
+        let data = log_entry_data(
+            DEPOSITED_EVENT,
+            DepositedEvent::event_params(),
+            &[
+                Token::String("alice.near".to_string()),
+                Token::Uint(1000.into()),
+                Token::Uint(10.into()),
+            ],
+        );
+        let event = DepositedEvent::from_log_entry_data(&data).unwrap();
+        assert!(
+            matches!(event.token_message_data, TokenMessageData::Near(ref account_id) if account_id.as_str() == "alice.near")
+        );
+        assert_eq!(event.amount, NEP141Wei::new(1000));
+        assert_eq!(event.message, None);
+    }
+
+    #[test]
+    fn test_parse_v2_near_event() {
+        let data = v2_log_entry_data([0u8; 20], "", "");
+        let event = DepositedEvent::from_log_entry_data(&data).unwrap();
+        assert!(
+            matches!(event.token_message_data, TokenMessageData::Near(ref account_id) if account_id.as_str() == "alice.near")
+        );
+        assert_eq!(event.amount, NEP141Wei::new(1000));
+        assert_eq!(event.fee.as_u128(), 10);
+        assert_eq!(event.message, None);
+    }
+
+    #[test]
+    fn test_parse_v2_evm_event() {
+        let data = v2_log_entry_data(
+            [2u8; 20],
+            "0x0303030303030303030303030303030303030303:dead",
+            "aurora",
+        );
+        let event = DepositedEvent::from_log_entry_data(&data).unwrap();
+        match event.token_message_data {
+            TokenMessageData::Eth {
+                receiver_id,
+                message,
+            } => {
+                assert_eq!(receiver_id.as_str(), "aurora");
+                assert_eq!(message.recipient, Address::from_array([2u8; 20]));
+                assert_eq!(message.fee.as_u128(), 10);
+                assert_eq!(
+                    message.evm_call,
+                    Some(EvmCallData {
+                        contract: Address::from_array([3u8; 20]),
+                        input: vec![0xde, 0xad],
+                    })
+                );
+            }
+            _ => panic!("Expected EVM deposit"),
+        }
+        assert_eq!(
+            event.message.as_deref(),
+            Some("0x0303030303030303030303030303030303030303:dead")
+        );
+    }
+
+    #[test]
+    fn test_parse_v2_evm_event_with_message() {
+        for message in ["hello", "memo: not a call"] {
+            let data = v2_log_entry_data([2u8; 20], message, "aurora");
+            let event = DepositedEvent::from_log_entry_data(&data).unwrap();
+            match event.token_message_data {
+                TokenMessageData::Eth { message, .. } => {
+                    assert_eq!(message.recipient, Address::from_array([2u8; 20]));
+                    assert_eq!(message.evm_call, None);
+                }
+                _ => panic!("Expected EVM deposit"),
+            }
+            assert_eq!(event.message.as_deref(), Some(message));
+        }
+    }
+
+    #[test]
+    fn test_parse_v2_near_call_event() {
+        let data = v2_log_entry_data([0u8; 20], "swap", "dex.near");
+        let event = DepositedEvent::from_log_entry_data(&data).unwrap();
+        match event.token_message_data {
+            TokenMessageData::NearCall {
+                account_id,
+                receiver_id,
+                msg,
+            } => {
+                assert_eq!(account_id.as_str(), "alice.near");
+                assert_eq!(receiver_id.as_str(), "dex.near");
+                assert_eq!(msg, "swap");
             }
+            _ => panic!("Expected NEAR deposit with the call"),
         }
+
+        let data = v2_log_entry_data([0u8; 20], "swap", "alice.near");
+        assert!(DepositedEvent::from_log_entry_data(&data).is_err());
+    }
+
+    #[test]
+    fn test_parse_event_message_with_evm_call() {
+        let message = "aurora:0202020202020202020202020202020202020202:0303030303030303030303030303030303030303:0xdead";
+        let data = TokenMessageData::parse_event_message_and_prepare_token_message_data(
+            message,
+            10.into(),
+        )
+        .unwrap();
+        match data {
+            TokenMessageData::Eth { message, .. } => {
+                assert_eq!(message.recipient, Address::from_array([2u8; 20]));
+                assert_eq!(
+                    message.evm_call.map(|evm_call| evm_call.input),
+                    Some(vec![0xde, 0xad])
+                );
+            }
+            _ => panic!("Expected EVM deposit"),
+        }
+
+        let message = "aurora:0202020202020202020202020202020202020202:dead";
+        assert!(matches!(
+            TokenMessageData::parse_event_message_and_prepare_token_message_data(
+                message,
+                10.into()
+            ),
+            Err(error::ParseEventMessageError::TooFewParts)
+        ));
+
+        let message = "aurora:0202020202020202020202020202020202020202:0303030303030303030303030303030303030303:dead:beef";
+        assert!(matches!(
+            TokenMessageData::parse_event_message_and_prepare_token_message_data(
+                message,
+                10.into()
+            ),
+            Err(error::ParseEventMessageError::TooManyParts)
+        ));
+    }
+
+    #[test]
+    fn test_parse_legacy_on_transfer_message() {
+        let message = FtTransferMessageData {
+            relayer: "relayer.near".parse().unwrap(),
+            recipient: Address::from_array([2u8; 20]),
+            fee: 10.into(),
+            evm_call: None,
+        };
+        let encoded = message.encode();
+        assert!(encoded.starts_with("relayer.near:"));
+        let parsed = FtTransferMessageData::parse_on_transfer_message(&encoded).unwrap();
+        assert_eq!(parsed.recipient, message.recipient);
+        assert_eq!(parsed.fee.as_u128(), 10);
+        assert_eq!(parsed.evm_call, None);
+    }
+
+    #[test]
+    fn test_parse_versioned_on_transfer_message() {
+        let message = FtTransferMessageData {
+            relayer: "relayer.near".parse().unwrap(),
+            recipient: Address::from_array([2u8; 20]),
+            fee: 10.into(),
+            evm_call: Some(EvmCallData {
+                contract: Address::from_array([3u8; 20]),
+                input: vec![0xde, 0xad],
+            }),
+        };
+        let encoded = message.encode();
+        assert!(encoded.starts_with('{'));
+        let parsed = FtTransferMessageData::parse_on_transfer_message(&encoded).unwrap();
+        assert_eq!(parsed.relayer, message.relayer);
+        assert_eq!(parsed.recipient, message.recipient);
+        assert_eq!(parsed.fee.as_u128(), 10);
+        assert_eq!(parsed.evm_call, message.evm_call);
+
+        let parsed = FtTransferMessageData::parse_on_transfer_message(
+            r#"{"version":1,"relayer":"relayer.near","fee":"5","recipient":"0x0202020202020202020202020202020202020202"}"#,
+        )
+        .unwrap();
+        assert_eq!(parsed.recipient, Address::from_array([2u8; 20]));
+        assert_eq!(parsed.evm_call, None);
+    }
+
+    #[test]
+    fn test_parse_unsupported_on_transfer_message_version() {
+        let result = FtTransferMessageData::parse_on_transfer_message(
+            r#"{"version":2,"relayer":"relayer.near","fee":"5","recipient":"0202020202020202020202020202020202020202"}"#,
+        );
+        assert!(matches!(
+            result,
+            Err(error::ParseOnTransferMessageError::UnsupportedVersion)
+        ));
     }
 }
@@ -0,0 +1,243 @@
+use aurora_engine_types::types::{Address, Fee, NEP141Wei};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    AccountId, BlockHeight, IntoStorageKey,
+};
+
+/// Default page size of the `deposits_of` view.
+pub const DEFAULT_DEPOSITS_OF_LIMIT: u64 = 50;
+
+/// Deposit history settings. The history is off by default.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositHistoryConfig {
+    pub enabled: bool,
+    /// Maximum number of records kept per account. The oldest records are dropped.
+    pub max_per_account: u32,
+}
+
+impl Default for DepositHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_per_account: 20,
+        }
+    }
+}
+
+/// Finished deposit record.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DepositRecord {
+    pub proof_key: String,
+    pub sender: Address,
+    pub recipient: AccountId,
+    /// The address credited in the engine, if the deposit is sent to the engine.
+    pub eth_recipient: Option<Address>,
+    pub amount: NEP141Wei,
+    pub fee: Fee,
+    pub relayer_id: AccountId,
+    pub block_height: BlockHeight,
+}
+
+/// JSON view of `DepositRecord`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositRecordView {
+    pub proof_key: String,
+    pub sender: String,
+    pub recipient: AccountId,
+    pub eth_recipient: Option<String>,
+    pub amount: U128,
+    pub fee: U128,
+    pub relayer_id: AccountId,
+    pub block_height: U64,
+}
+
+impl From<DepositRecord> for DepositRecordView {
+    fn from(record: DepositRecord) -> Self {
+        Self {
+            proof_key: record.proof_key,
+            sender: record.sender.encode(),
+            recipient: record.recipient,
+            eth_recipient: record.eth_recipient.map(|address| address.encode()),
+            amount: record.amount.as_u128().into(),
+            fee: record.fee.as_u128().into(),
+            relayer_id: record.relayer_id,
+            block_height: record.block_height.into(),
+        }
+    }
+}
+
+/// Bounded per-account log of finished deposits.
+/// The deposits sent to the engine are logged under the credited address
+/// instead of the engine account.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct DepositHistory {
+    pub config: DepositHistoryConfig,
+    /// Deposit records by proof key
+    pub records: LookupMap<String, DepositRecord>,
+    /// Proof keys of the account deposits, from the oldest to the newest
+    pub accounts: LookupMap<AccountId, Vec<String>>,
+    /// Proof keys of the deposits to the engine addresses, from the oldest to the newest
+    pub addresses: LookupMap<Address, Vec<String>>,
+}
+
+impl DepositHistory {
+    pub fn new<S: IntoStorageKey>(
+        prefix_records: S,
+        prefix_accounts: S,
+        prefix_addresses: S,
+    ) -> Self {
+        Self {
+            config: DepositHistoryConfig::default(),
+            records: LookupMap::new(prefix_records),
+            accounts: LookupMap::new(prefix_accounts),
+            addresses: LookupMap::new(prefix_addresses),
+        }
+    }
+
+    /// Add the record to the recipient log if the history is enabled.
+    pub fn record(&mut self, record: &DepositRecord) {
+        if !self.config.enabled || self.config.max_per_account == 0 {
+            return;
+        }
+        let mut proof_keys = match &record.eth_recipient {
+            Some(address) => self.addresses.get(address),
+            None => self.accounts.get(&record.recipient),
+        }
+        .unwrap_or_default();
+        proof_keys.push(record.proof_key.clone());
+        let max_per_account = self.config.max_per_account as usize;
+        if proof_keys.len() > max_per_account {
+            let dropped = proof_keys.len() - max_per_account;
+            for proof_key in proof_keys.drain(..dropped) {
+                self.records.remove(&proof_key);
+            }
+        }
+        self.records.insert(&record.proof_key, record);
+        match &record.eth_recipient {
+            Some(address) => self.addresses.insert(address, &proof_keys),
+            None => self.accounts.insert(&record.recipient, &proof_keys),
+        };
+    }
+
+    pub fn get(&self, proof_key: &str) -> Option<DepositRecord> {
+        self.records.get(&proof_key.to_string())
+    }
+
+    /// Return records of the account from the oldest to the newest.
+    pub fn deposits_of(
+        &self,
+        account_id: &AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<DepositRecord> {
+        self.page(
+            self.accounts.get(account_id).unwrap_or_default(),
+            from_index,
+            limit,
+        )
+    }
+
+    /// Return records of the engine address from the oldest to the newest.
+    pub fn deposits_of_address(
+        &self,
+        address: &Address,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<DepositRecord> {
+        self.page(
+            self.addresses.get(address).unwrap_or_default(),
+            from_index,
+            limit,
+        )
+    }
+
+    fn page(&self, proof_keys: Vec<String>, from_index: u64, limit: u64) -> Vec<DepositRecord> {
+        proof_keys
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|proof_key| self.records.get(proof_key))
+            .collect()
+    }
+}
+
+#[ext_contract(ext_deposit_history)]
+pub trait DepositHistoryView {
+    /// Set the deposit history settings. Records over the new limit
+    /// are dropped on the next deposit of the account.
+    fn set_deposit_history_config(&mut self, config: DepositHistoryConfig);
+
+    fn get_deposit_history_config(&self) -> DepositHistoryConfig;
+
+    /// Return deposits of the account from the oldest to the newest.
+    /// The deposits sent to the engine aren't included, see `deposits_of_address`.
+    fn deposits_of(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<DepositRecordView>;
+
+    /// Return deposits credited to the engine address from the oldest to the newest.
+    /// The address is a hex string with an optional `0x` prefix.
+    fn deposits_of_address(
+        &self,
+        address: String,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<DepositRecordView>;
+
+    fn deposit_by_proof_key(&self, proof_key: String) -> Option<DepositRecordView>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn record(proof_key: &str, eth_recipient: Option<Address>) -> DepositRecord {
+        DepositRecord {
+            proof_key: proof_key.to_string(),
+            sender: Address::default(),
+            recipient: "aurora".parse().unwrap(),
+            eth_recipient,
+            amount: NEP141Wei::new(100),
+            fee: Fee::new(NEP141Wei::new(0)),
+            relayer_id: "relayer".parse().unwrap(),
+            block_height: 1,
+        }
+    }
+
+    #[test]
+    fn test_eth_recipient_deposits() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut history = DepositHistory::new(b"r".to_vec(), b"a".to_vec(), b"e".to_vec());
+        history.config = DepositHistoryConfig {
+            enabled: true,
+            max_per_account: 2,
+        };
+        let address = Address::decode("096de9c2b8a5b8c22cee3289b101f6960d68e51e").unwrap();
+        for proof_key in ["1", "2", "3"] {
+            history.record(&record(proof_key, Some(address)));
+        }
+        history.record(&record("4", None));
+
+        let proof_keys: Vec<String> = history
+            .deposits_of_address(&address, 0, 10)
+            .into_iter()
+            .map(|record| record.proof_key)
+            .collect();
+        assert_eq!(proof_keys, ["2", "3"]);
+        assert!(history.get("1").is_none());
+        // The engine account log has only the deposits to the engine account itself
+        let aurora: AccountId = "aurora".parse().unwrap();
+        assert_eq!(history.deposits_of(&aurora, 0, 10).len(), 1);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::admin_controlled::{PausedMask, UNPAUSE_ALL};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    AccountId, Balance, IntoStorageKey,
+};
+
+/// Default page size of the engines view.
+pub const DEFAULT_ENGINES_LIMIT: u64 = 50;
+
+/// Registered engine data.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EngineInfo {
+    /// Paused flags of the engine. `PAUSE_DEPOSIT` refuses deposits to the engine,
+    /// `PAUSE_WITHDRAW` refuses withdrawals made by the engine.
+    pub paused_mask: PausedMask,
+    /// Amount of tokens delivered to the engine with transfer calls minus
+    /// the amount withdrawn by the engine. It's informational: the engine may also
+    /// receive tokens with plain transfers, so the withdrawals aren't limited by it.
+    pub supply: U128,
+}
+
+/// JSON view of the registered engine
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EngineView {
+    pub engine_id: AccountId,
+    pub paused_mask: PausedMask,
+    pub supply: U128,
+}
+
+/// Registry of Aurora engines served by the connector. Registered engines are allowed
+/// as the deposit targets and may call the engine entry points, in addition to
+/// the account with access right. Unlike that account, an engine acts only for itself
+/// and for the accounts which gave it an allowance.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct EngineRegistry {
+    pub engines: UnorderedMap<AccountId, EngineInfo>,
+}
+
+impl EngineRegistry {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            engines: UnorderedMap::new(prefix),
+        }
+    }
+
+    pub fn get(&self, engine_id: &AccountId) -> Option<EngineInfo> {
+        self.engines.get(engine_id)
+    }
+
+    pub fn is_registered(&self, engine_id: &AccountId) -> bool {
+        self.engines.get(engine_id).is_some()
+    }
+
+    pub fn register(&mut self, engine_id: &AccountId) -> Result<(), error::EngineError> {
+        if self.is_registered(engine_id) {
+            return Err(error::EngineError::AlreadyRegistered);
+        }
+        self.engines.insert(
+            engine_id,
+            &EngineInfo {
+                paused_mask: UNPAUSE_ALL,
+                supply: 0.into(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove the engine and return its data.
+    pub fn unregister(&mut self, engine_id: &AccountId) -> Result<EngineInfo, error::EngineError> {
+        self.engines
+            .remove(engine_id)
+            .ok_or(error::EngineError::NotRegistered)
+    }
+
+    pub fn set_paused_flags(
+        &mut self,
+        engine_id: &AccountId,
+        paused_mask: PausedMask,
+    ) -> Result<(), error::EngineError> {
+        let mut engine = self
+            .get(engine_id)
+            .ok_or(error::EngineError::NotRegistered)?;
+        engine.paused_mask = paused_mask;
+        self.engines.insert(engine_id, &engine);
+        Ok(())
+    }
+
+    /// Check that the flow of the engine isn't paused. Unregistered accounts are skipped.
+    pub fn assert_not_paused(
+        &self,
+        engine_id: &AccountId,
+        flag: PausedMask,
+    ) -> Result<(), error::EngineError> {
+        match self.get(engine_id) {
+            Some(engine) if engine.paused_mask & flag != 0 => Err(error::EngineError::Paused),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the deposit can be sent to the receiver. `default_engine_id` is
+    /// the account with access right, which is always allowed.
+    pub fn assert_deposit_target(
+        &self,
+        receiver_id: &AccountId,
+        default_engine_id: &AccountId,
+        flag: PausedMask,
+    ) -> Result<(), error::EngineError> {
+        if receiver_id != default_engine_id && !self.is_registered(receiver_id) {
+            return Err(error::EngineError::NotRegistered);
+        }
+        self.assert_not_paused(receiver_id, flag)
+    }
+
+    /// Add the amount delivered to the engine. Unregistered accounts are skipped.
+    pub fn record_deposit(
+        &mut self,
+        engine_id: &AccountId,
+        amount: Balance,
+    ) -> Result<(), error::EngineError> {
+        if let Some(mut engine) = self.get(engine_id) {
+            engine.supply = engine
+                .supply
+                .0
+                .checked_add(amount)
+                .ok_or(error::EngineError::SupplyOverflow)?
+                .into();
+            self.engines.insert(engine_id, &engine);
+        }
+        Ok(())
+    }
+
+    /// Subtract the amount withdrawn by the engine. The supply doesn't go below zero,
+    /// because the withdrawn tokens may be received with plain transfers.
+    /// Unregistered accounts are skipped.
+    pub fn record_withdraw(&mut self, engine_id: &AccountId, amount: Balance) {
+        if let Some(mut engine) = self.get(engine_id) {
+            engine.supply = engine.supply.0.saturating_sub(amount).into();
+            self.engines.insert(engine_id, &engine);
+        }
+    }
+
+    /// Return a page of the registered engines.
+    pub fn page(&self, from_index: u64, limit: u64) -> Vec<EngineView> {
+        self.engines
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|(engine_id, engine)| EngineView {
+                engine_id,
+                paused_mask: engine.paused_mask,
+                supply: engine.supply,
+            })
+            .collect()
+    }
+}
+
+#[ext_contract(ext_engine_registry)]
+pub trait EngineManagement {
+    /// Register the engine. It gets access to the engine entry points and becomes a deposit target.
+    fn register_engine(&mut self, engine_id: AccountId);
+
+    fn unregister_engine(&mut self, engine_id: AccountId) -> EngineInfo;
+
+    /// Set the paused flags of the engine.
+    fn set_engine_paused_flags(&mut self, engine_id: AccountId, paused_mask: PausedMask);
+
+    fn get_engine(&self, engine_id: AccountId) -> Option<EngineInfo>;
+
+    /// Return registered engines starting from `from_index`, at most `limit` items.
+    fn get_engines(&self, from_index: Option<U64>, limit: Option<U64>) -> Vec<EngineView>;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum EngineError {
+        NotRegistered,
+        AlreadyRegistered,
+        Paused,
+        SupplyOverflow,
+    }
+
+    impl AsRef<[u8]> for EngineError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::NotRegistered => errors::ERR_ENGINE_NOT_REGISTERED,
+                Self::AlreadyRegistered => errors::ERR_ENGINE_ALREADY_REGISTERED,
+                Self::Paused => errors::ERR_ENGINE_PAUSED,
+                Self::SupplyOverflow => errors::ERR_ENGINE_SUPPLY_OVERFLOW,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::admin_controlled::PAUSE_DEPOSIT;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn account(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_deposit_target() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut registry = EngineRegistry::new(b"e".to_vec());
+        let aurora = account("aurora");
+        let silo = account("silo.aurora");
+        assert!(registry
+            .assert_deposit_target(&aurora, &aurora, PAUSE_DEPOSIT)
+            .is_ok());
+        assert!(matches!(
+            registry.assert_deposit_target(&silo, &aurora, PAUSE_DEPOSIT),
+            Err(error::EngineError::NotRegistered)
+        ));
+
+        registry.register(&silo).unwrap();
+        assert!(registry
+            .assert_deposit_target(&silo, &aurora, PAUSE_DEPOSIT)
+            .is_ok());
+        registry.set_paused_flags(&silo, PAUSE_DEPOSIT).unwrap();
+        assert!(matches!(
+            registry.assert_deposit_target(&silo, &aurora, PAUSE_DEPOSIT),
+            Err(error::EngineError::Paused)
+        ));
+    }
+
+    #[test]
+    fn test_supply() {
+        testing_env!(VMContextBuilder::new().build());
+        let mut registry = EngineRegistry::new(b"e".to_vec());
+        let silo = account("silo.aurora");
+        registry.register(&silo).unwrap();
+        registry.record_deposit(&silo, 100).unwrap();
+        registry.record_withdraw(&silo, 30);
+        assert_eq!(registry.get(&silo).unwrap().supply, 70.into());
+        assert!(matches!(
+            registry.record_deposit(&silo, u128::MAX),
+            Err(error::EngineError::SupplyOverflow)
+        ));
+        assert_eq!(registry.get(&silo).unwrap().supply, 70.into());
+        registry.record_withdraw(&silo, 100);
+        assert_eq!(registry.get(&silo).unwrap().supply, 0.into());
+        // Unregistered accounts are skipped
+        registry.record_deposit(&account("alice"), 100).unwrap();
+        assert!(!registry.is_registered(&account("alice")));
+    }
+}
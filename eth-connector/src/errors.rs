@@ -27,6 +27,8 @@ pub const ERR_VALUE_CONVERSION: &str = "ERR_VALUE_CONVERSION";
 pub const ERR_BORSH_DESERIALIZE: &str = "ERR_BORSH_DESERIALIZE";
 pub const ERR_BORSH_SERIALIZE: &str = "ERR_BORSH_SERIALIZE";
 pub const ERR_META_TX_PARSE: &str = "ERR_META_TX_PARSE";
+pub const ERR_STATE_ALREADY_MIGRATED: &str = "ERR_STATE_ALREADY_MIGRATED";
+pub const ERR_STATE_NOT_INITIALIZED: &str = "ERR_STATE_NOT_INITIALIZED";
 
 pub const ERR_STACK_UNDERFLOW: &[u8; 19] = b"ERR_STACK_UNDERFLOW";
 pub const ERR_STACK_OVERFLOW: &[u8; 18] = b"ERR_STACK_OVERFLOW";
@@ -70,8 +72,41 @@ pub const ERR_INVALID_ON_TRANSFER_MESSAGE_FORMAT: &[u8; 38] =
     b"ERR_INVALID_ON_TRANSFER_MESSAGE_FORMAT";
 pub const ERR_INVALID_ON_TRANSFER_MESSAGE_HEX: &[u8; 35] = b"ERR_INVALID_ON_TRANSFER_MESSAGE_HEX";
 pub const ERR_INVALID_ON_TRANSFER_MESSAGE_DATA: &[u8; 36] = b"ERR_INVALID_ON_TRANSFER_MESSAGE_DATA";
+pub const ERR_INVALID_ON_TRANSFER_MESSAGE_JSON: &[u8; 36] = b"ERR_INVALID_ON_TRANSFER_MESSAGE_JSON";
+pub const ERR_UNSUPPORTED_ON_TRANSFER_MESSAGE_VERSION: &[u8; 43] =
+    b"ERR_UNSUPPORTED_ON_TRANSFER_MESSAGE_VERSION";
 pub const ERR_INVALID_ACCOUNT_ID: &[u8; 22] = b"ERR_INVALID_ACCOUNT_ID";
 pub const ERR_OVERFLOW_NUMBER: &[u8; 19] = b"ERR_OVERFLOW_NUMBER";
+pub const ERR_FEE_TOO_HIGH: &[u8; 16] = b"ERR_FEE_TOO_HIGH";
+pub const ERR_NET_DEPOSIT_TOO_LOW: &[u8; 23] = b"ERR_NET_DEPOSIT_TOO_LOW";
+pub const ERR_INVALID_BASIS_POINTS: &[u8; 24] = b"ERR_INVALID_BASIS_POINTS";
+pub const ERR_TREASURY_NOT_SET: &[u8; 20] = b"ERR_TREASURY_NOT_SET";
+pub const ERR_RELAYER_NOT_REGISTERED: &[u8; 26] = b"ERR_RELAYER_NOT_REGISTERED";
+pub const ERR_RELAYER_STAKE_TOO_LOW: &[u8; 25] = b"ERR_RELAYER_STAKE_TOO_LOW";
+pub const ERR_RELAYER_UNBONDING: &[u8; 21] = b"ERR_RELAYER_UNBONDING";
+pub const ERR_RELAYER_NOT_UNBONDING: &[u8; 25] = b"ERR_RELAYER_NOT_UNBONDING";
+pub const ERR_RELAYER_STAKE_LOCKED: &[u8; 24] = b"ERR_RELAYER_STAKE_LOCKED";
+pub const ERR_INVALID_MINT_WINDOW: &[u8; 23] = b"ERR_INVALID_MINT_WINDOW";
+pub const ERR_PENDING_DEPOSIT_NOT_FOUND: &[u8; 29] = b"ERR_PENDING_DEPOSIT_NOT_FOUND";
+pub const ERR_WITHDRAW_FEE_TOO_HIGH: &[u8; 25] = b"ERR_WITHDRAW_FEE_TOO_HIGH";
+pub const ERR_FEE_COLLECTOR_NOT_SET: &[u8; 25] = b"ERR_FEE_COLLECTOR_NOT_SET";
+pub const ERR_FEE_COLLECTOR_NOT_REGISTERED: &[u8; 32] = b"ERR_FEE_COLLECTOR_NOT_REGISTERED";
+pub const ERR_WITHDRAW_NOT_QUEUED: &[u8; 23] = b"ERR_WITHDRAW_NOT_QUEUED";
+pub const ERR_NOT_GUARDIAN: &[u8; 16] = b"ERR_NOT_GUARDIAN";
+pub const ERR_ENGINE_NOT_REGISTERED: &[u8; 25] = b"ERR_ENGINE_NOT_REGISTERED";
+pub const ERR_ENGINE_ALREADY_REGISTERED: &[u8; 29] = b"ERR_ENGINE_ALREADY_REGISTERED";
+pub const ERR_ENGINE_PAUSED: &[u8; 17] = b"ERR_ENGINE_PAUSED";
+pub const ERR_ENGINE_SUPPLY_OVERFLOW: &[u8; 26] = b"ERR_ENGINE_SUPPLY_OVERFLOW";
+pub const ERR_INSUFFICIENT_ALLOWANCE: &[u8; 26] = b"ERR_INSUFFICIENT_ALLOWANCE";
+pub const ERR_SELF_APPROVAL: &[u8; 17] = b"ERR_SELF_APPROVAL";
+pub const ERR_INVALID_SIGNATURE: &[u8; 21] = b"ERR_INVALID_SIGNATURE";
+pub const ERR_INVALID_NONCE: &[u8; 17] = b"ERR_INVALID_NONCE";
+pub const ERR_INVALID_CONTRACT_ID: &[u8; 23] = b"ERR_INVALID_CONTRACT_ID";
+pub const ERR_AUTHORIZATION_EXPIRED: &[u8; 25] = b"ERR_AUTHORIZATION_EXPIRED";
+pub const ERR_UNKNOWN_SIGNING_KEY: &[u8; 23] = b"ERR_UNKNOWN_SIGNING_KEY";
+pub const ERR_TOO_MANY_ACCOUNTS: &[u8; 21] = b"ERR_TOO_MANY_ACCOUNTS";
+pub const ERR_EMPTY_BATCH: &[u8; 15] = b"ERR_EMPTY_BATCH";
+pub const ERR_TOO_MANY_TRANSFERS: &[u8; 22] = b"ERR_TOO_MANY_TRANSFERS";
 
 pub const ERR_TOTAL_SUPPLY_OVERFLOW: &str = "ERR_TOTAL_SUPPLY_OVERFLOW";
 pub const ERR_NOT_ENOUGH_BALANCE: &[u8; 22] = b"ERR_NOT_ENOUGH_BALANCE";
@@ -83,6 +118,7 @@ pub const ERR_NO_AVAILABLE_BALANCE: &[u8; 24] = b"ERR_NO_AVAILABLE_BALANCE";
 pub const ERR_ATTACHED_DEPOSIT_NOT_ENOUGH: &[u8; 31] = b"ERR_ATTACHED_DEPOSIT_NOT_ENOUGH";
 pub const ERR_FAILED_UNREGISTER_ACCOUNT_POSITIVE_BALANCE: &[u8; 46] =
     b"ERR_FAILED_UNREGISTER_ACCOUNT_POSITIVE_BALANCE";
+pub const ERR_PENDING_TRANSFER_CALLS: &[u8; 26] = b"ERR_PENDING_TRANSFER_CALLS";
 
 pub const ERR_ACCOUNTS_COUNTER_OVERFLOW: &str = "ERR_ACCOUNTS_COUNTER_OVERFLOW";
 
@@ -93,5 +129,6 @@ pub const ERR_NOT_ENOUGH_BALANCE_FOR_FEE: &str = "ERR_NOT_ENOUGH_BALANCE_FOR_FEE
 
 pub const ERR_MORE_GAS_REQUIRED: &str = "ERR_MORE_GAS_REQUIRED";
 pub const ERR_PREPAID_GAS_OVERFLOW: &str = "ERR_PREPAID_GAS_OVERFLOW";
+pub const ERR_WITHDRAW_DELAYED: &str = "ERR_WITHDRAW_DELAYED";
 pub const ERR_RECEIVER_BALANCE_NOT_ENOUGH: &str = "ERR__RECEIVER_BALANCE_NOT_ENOUGH";
 pub const ERR_USED_AMOUNT_OVERFLOW: &str = "ERR_USED_AMOUNT_OVERFLOW";
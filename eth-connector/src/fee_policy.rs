@@ -0,0 +1,286 @@
+use aurora_engine_types::{
+    types::{Fee, NEP141Wei},
+    U256,
+};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    ext_contract,
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Basis points in 100%.
+pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+/// How deposits whose fee breaks the policy are handled.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum FeePolicyMode {
+    /// The deposit fails.
+    Reject,
+    /// The relayer fee is lowered to the allowed maximum.
+    Clamp,
+}
+
+/// Relayer fee rules for deposits. The default policy has no limits and no protocol fee.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeePolicy {
+    /// Maximum fee in absolute terms.
+    pub max_fee: Option<U128>,
+    /// Maximum fee in basis points of the deposited amount.
+    pub max_fee_bps: Option<u16>,
+    /// Minimum amount the recipient should get after the fee.
+    pub min_net_deposit: Option<U128>,
+    /// Share of the fee in basis points which is paid to the treasury instead of the relayer.
+    pub protocol_fee_bps: u16,
+    /// Account receiving the protocol fee share.
+    pub treasury_id: Option<AccountId>,
+    pub mode: FeePolicyMode,
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        Self {
+            max_fee: None,
+            max_fee_bps: None,
+            min_net_deposit: None,
+            protocol_fee_bps: 0,
+            treasury_id: None,
+            mode: FeePolicyMode::Reject,
+        }
+    }
+}
+
+/// Protocol share of the deposit fee.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct ProtocolFee {
+    pub treasury_id: AccountId,
+    pub amount: Fee,
+}
+
+/// Deposit fee after the policy was applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositFee {
+    pub relayer_fee: Fee,
+    pub protocol_fee: Option<ProtocolFee>,
+}
+
+impl DepositFee {
+    /// Total fee: relayer fee and protocol fee.
+    pub fn total(&self) -> NEP141Wei {
+        let protocol_fee = self
+            .protocol_fee
+            .as_ref()
+            .map_or(0, |protocol_fee| protocol_fee.amount.as_u128());
+        NEP141Wei::new(self.relayer_fee.as_u128() + protocol_fee)
+    }
+}
+
+impl FeePolicy {
+    pub fn validate(&self) -> Result<(), error::FeePolicyError> {
+        let max_fee_bps = self.max_fee_bps.unwrap_or(0);
+        if max_fee_bps > MAX_BASIS_POINTS || self.protocol_fee_bps > MAX_BASIS_POINTS {
+            return Err(error::FeePolicyError::InvalidBasisPoints);
+        }
+        if self.protocol_fee_bps > 0 && self.treasury_id.is_none() {
+            return Err(error::FeePolicyError::MissingTreasury);
+        }
+        Ok(())
+    }
+
+    /// Maximum fee allowed for the deposited amount.
+    pub fn max_fee_for(&self, amount: NEP141Wei) -> Option<u128> {
+        let max_fee_bps = self
+            .max_fee_bps
+            .map(|bps| basis_points(amount.as_u128(), bps));
+        match (self.max_fee.map(|fee| fee.0), max_fee_bps) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Apply the policy to the fee signed by the Ethereum user.
+    /// The fee is expected to be lower than the amount.
+    pub fn apply(&self, amount: NEP141Wei, fee: Fee) -> Result<DepositFee, error::FeePolicyError> {
+        let amount = amount.as_u128();
+        let mut fee = fee.as_u128();
+
+        if let Some(max_fee) = self.max_fee_for(NEP141Wei::new(amount)) {
+            if fee > max_fee {
+                match self.mode {
+                    FeePolicyMode::Reject => return Err(error::FeePolicyError::FeeTooHigh),
+                    FeePolicyMode::Clamp => fee = max_fee,
+                }
+            }
+        }
+
+        if let Some(min_net_deposit) = self.min_net_deposit.map(|value| value.0) {
+            if amount.saturating_sub(fee) < min_net_deposit {
+                match self.mode {
+                    FeePolicyMode::Clamp if amount >= min_net_deposit => {
+                        fee = amount - min_net_deposit;
+                    }
+                    _ => return Err(error::FeePolicyError::NetDepositTooLow),
+                }
+            }
+        }
+
+        let protocol_fee = basis_points(fee, self.protocol_fee_bps);
+        let protocol_fee = match &self.treasury_id {
+            Some(treasury_id) if protocol_fee > 0 => Some(ProtocolFee {
+                treasury_id: treasury_id.clone(),
+                amount: protocol_fee.into(),
+            }),
+            _ => None,
+        };
+        let relayer_fee = fee - protocol_fee.as_ref().map_or(0, |f| f.amount.as_u128());
+
+        Ok(DepositFee {
+            relayer_fee: relayer_fee.into(),
+            protocol_fee,
+        })
+    }
+}
+
+/// Calculate `bps` basis points of the value.
+pub(crate) fn basis_points(value: u128, bps: u16) -> u128 {
+    (U256::from(value) * U256::from(bps) / U256::from(MAX_BASIS_POINTS)).as_u128()
+}
+
+#[ext_contract(ext_fee_policy)]
+pub trait FeePolicyManagement {
+    /// Set the relayer fee policy for deposits.
+    fn set_fee_policy(&mut self, policy: FeePolicy);
+
+    /// Return the relayer fee policy for deposits.
+    fn get_fee_policy(&self) -> FeePolicy;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum FeePolicyError {
+        FeeTooHigh,
+        NetDepositTooLow,
+        InvalidBasisPoints,
+        MissingTreasury,
+    }
+
+    impl AsRef<[u8]> for FeePolicyError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::FeeTooHigh => errors::ERR_FEE_TOO_HIGH,
+                Self::NetDepositTooLow => errors::ERR_NET_DEPOSIT_TOO_LOW,
+                Self::InvalidBasisPoints => errors::ERR_INVALID_BASIS_POINTS,
+                Self::MissingTreasury => errors::ERR_TREASURY_NOT_SET,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn treasury() -> AccountId {
+        AccountId::new_unchecked("treasury".to_string())
+    }
+
+    fn policy(mode: FeePolicyMode) -> FeePolicy {
+        FeePolicy {
+            max_fee: Some(U128(500)),
+            max_fee_bps: Some(100),
+            min_net_deposit: Some(U128(900)),
+            protocol_fee_bps: 0,
+            treasury_id: None,
+            mode,
+        }
+    }
+
+    #[test]
+    fn test_default_policy_keeps_fee() {
+        let fee = FeePolicy::default()
+            .apply(NEP141Wei::new(1000), 999.into())
+            .unwrap();
+        assert_eq!(fee.relayer_fee, 999.into());
+        assert_eq!(fee.protocol_fee, None);
+    }
+
+    #[test]
+    fn test_max_fee_is_the_lowest_limit() {
+        let policy = policy(FeePolicyMode::Reject);
+        assert_eq!(policy.max_fee_for(NEP141Wei::new(10_000)), Some(100));
+        assert_eq!(policy.max_fee_for(NEP141Wei::new(100_000)), Some(500));
+    }
+
+    #[test]
+    fn test_reject_mode() {
+        let policy = policy(FeePolicyMode::Reject);
+        assert!(policy.apply(NEP141Wei::new(10_000), 100.into()).is_ok());
+        assert!(matches!(
+            policy.apply(NEP141Wei::new(10_000), 101.into()),
+            Err(error::FeePolicyError::FeeTooHigh)
+        ));
+        assert!(matches!(
+            policy.apply(NEP141Wei::new(905), 9.into()),
+            Err(error::FeePolicyError::NetDepositTooLow)
+        ));
+    }
+
+    #[test]
+    fn test_clamp_mode() {
+        let policy = policy(FeePolicyMode::Clamp);
+        let fee = policy.apply(NEP141Wei::new(10_000), 1_000.into()).unwrap();
+        assert_eq!(fee.relayer_fee, 100.into());
+        let fee = policy.apply(NEP141Wei::new(905), 9.into()).unwrap();
+        assert_eq!(fee.relayer_fee, 5.into());
+        assert!(matches!(
+            policy.apply(NEP141Wei::new(800), 1.into()),
+            Err(error::FeePolicyError::NetDepositTooLow)
+        ));
+    }
+
+    #[test]
+    fn test_protocol_fee_share() {
+        let policy = FeePolicy {
+            protocol_fee_bps: 2_500,
+            treasury_id: Some(treasury()),
+            ..FeePolicy::default()
+        };
+        let fee = policy.apply(NEP141Wei::new(10_000), 400.into()).unwrap();
+        assert_eq!(fee.relayer_fee, 300.into());
+        assert_eq!(
+            fee.protocol_fee,
+            Some(ProtocolFee {
+                treasury_id: treasury(),
+                amount: 100.into(),
+            })
+        );
+        assert_eq!(fee.total(), NEP141Wei::new(400));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut policy = FeePolicy {
+            protocol_fee_bps: 100,
+            ..FeePolicy::default()
+        };
+        assert!(matches!(
+            policy.validate(),
+            Err(error::FeePolicyError::MissingTreasury)
+        ));
+        policy.treasury_id = Some(treasury());
+        assert!(policy.validate().is_ok());
+        policy.max_fee_bps = Some(MAX_BASIS_POINTS + 1);
+        assert!(matches!(
+            policy.validate(),
+            Err(error::FeePolicyError::InvalidBasisPoints)
+        ));
+    }
+}
@@ -0,0 +1,338 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::{LookupMap, TreeMap},
+    env, ext_contract,
+    json_types::{U128, U64},
+    AccountId, Balance, BlockHeight, IntoStorageKey, StorageUsage,
+};
+use std::ops::Bound;
+
+/// Storage used by a single serialized checkpoint.
+pub const CHECKPOINT_STORAGE_USAGE: StorageUsage = 24;
+
+/// Minimum number of checkpoints kept for an account, so the balance before the latest
+/// change is known.
+pub const MIN_ACCOUNT_CHECKPOINTS: usize = 2;
+
+/// Maximum number of checkpoints kept for an account. The oldest checkpoints are dropped
+/// when the checkpoints would use more storage than the account registration is paid for.
+pub fn max_account_checkpoints(account_storage_usage: StorageUsage) -> usize {
+    usize::try_from(account_storage_usage / CHECKPOINT_STORAGE_USAGE)
+        .unwrap_or(usize::MAX)
+        .max(MIN_ACCOUNT_CHECKPOINTS)
+}
+
+/// Balance at the end of the block.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub block_height: BlockHeight,
+    pub balance: Balance,
+}
+
+/// Historical balances of the accounts and the total supply.
+///
+/// The history is kept from the block the tracking was enabled at: for all accounts and
+/// the total supply with `track_all_since`, or for the single account with `tracked_accounts`.
+/// Checkpoints recorded before the tracking was disabled and enabled again are stale and
+/// ignored, because the changes made in the meantime weren't recorded.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct BalanceCheckpoints {
+    pub accounts: LookupMap<AccountId, Vec<Checkpoint>>,
+    /// Accounts tracked individually and the block heights the tracking started at.
+    pub tracked_accounts: LookupMap<AccountId, BlockHeight>,
+    pub total_supply: TreeMap<BlockHeight, Balance>,
+    pub track_all_since: Option<BlockHeight>,
+}
+
+impl BalanceCheckpoints {
+    pub fn new<S: IntoStorageKey>(
+        prefix_accounts: S,
+        prefix_tracked_accounts: S,
+        prefix_total_supply: S,
+    ) -> Self {
+        Self {
+            accounts: LookupMap::new(prefix_accounts),
+            tracked_accounts: LookupMap::new(prefix_tracked_accounts),
+            total_supply: TreeMap::new(prefix_total_supply),
+            track_all_since: None,
+        }
+    }
+
+    /// The block height the continuous history of the account starts at.
+    pub fn tracking_since(&self, account_id: &AccountId) -> Option<BlockHeight> {
+        match (self.tracked_accounts.get(account_id), self.track_all_since) {
+            (Some(since), Some(all_since)) => Some(since.min(all_since)),
+            (since, all_since) => since.or(all_since),
+        }
+    }
+
+    pub fn set_track_all(&mut self, enabled: bool) {
+        self.track_all_since = match (enabled, self.track_all_since) {
+            (true, None) => Some(env::block_height()),
+            (true, since) => since,
+            (false, _) => None,
+        };
+    }
+
+    pub fn set_track_account(&mut self, account_id: &AccountId, enabled: bool, balance: Balance) {
+        if !enabled {
+            self.tracked_accounts.remove(account_id);
+            if self.tracking_since(account_id).is_none() {
+                self.accounts.remove(account_id);
+            }
+        } else if self.tracking_since(account_id).is_none() {
+            let block_height = env::block_height();
+            self.tracked_accounts.insert(account_id, &block_height);
+            self.accounts.insert(
+                account_id,
+                &vec![Checkpoint {
+                    block_height,
+                    balance,
+                }],
+            );
+        } else if !self.tracked_accounts.contains_key(account_id) {
+            // The account is already tracked by the global tracking, so the history is continuous
+            self.tracked_accounts
+                .insert(account_id, &env::block_height());
+        }
+    }
+
+    /// Record the new balance of the tracked account. Only the latest `max_checkpoints`
+    /// checkpoints are kept.
+    pub fn record_balance(
+        &mut self,
+        account_id: &AccountId,
+        since: BlockHeight,
+        old_balance: Balance,
+        new_balance: Balance,
+        max_checkpoints: usize,
+    ) {
+        let mut checkpoints = self.accounts.get(account_id).unwrap_or_default();
+        if !is_valid(&checkpoints, since) {
+            // The balance hasn't changed since the tracking was enabled
+            checkpoints = vec![Checkpoint {
+                block_height: since,
+                balance: old_balance,
+            }];
+        }
+        let block_height = env::block_height();
+        match checkpoints.last_mut() {
+            Some(last) if last.block_height == block_height => last.balance = new_balance,
+            _ => checkpoints.push(Checkpoint {
+                block_height,
+                balance: new_balance,
+            }),
+        }
+        if checkpoints.len() > max_checkpoints {
+            checkpoints.drain(..checkpoints.len() - max_checkpoints);
+        }
+        self.accounts.insert(account_id, &checkpoints);
+    }
+
+    /// Record the new total supply while all accounts are tracked.
+    pub fn record_total_supply(&mut self, old_supply: Balance, new_supply: Balance) {
+        if let Some(since) = self.track_all_since {
+            if !matches!(self.total_supply.max(), Some(last) if last >= since) {
+                self.total_supply.insert(&since, &old_supply);
+            }
+            self.total_supply.insert(&env::block_height(), &new_supply);
+        }
+    }
+
+    /// Return the balance of the account at the end of the block, or `None` if the history
+    /// of the account doesn't reach the block.
+    pub fn balance_at(
+        &self,
+        account_id: &AccountId,
+        current_balance: Balance,
+        block_height: BlockHeight,
+    ) -> Option<Balance> {
+        let since = self.tracking_since(account_id)?;
+        if block_height < since || block_height > env::block_height() {
+            return None;
+        }
+        let checkpoints = self.accounts.get(account_id).unwrap_or_default();
+        if !is_valid(&checkpoints, since) {
+            return Some(current_balance);
+        }
+        checkpoints
+            .iter()
+            .rev()
+            .find(|checkpoint| checkpoint.block_height <= block_height)
+            .map(|checkpoint| checkpoint.balance)
+    }
+
+    /// Return the total supply at the end of the block, or `None` if the history
+    /// doesn't reach the block.
+    pub fn total_supply_at(
+        &self,
+        current_supply: Balance,
+        block_height: BlockHeight,
+    ) -> Option<Balance> {
+        let since = self.track_all_since?;
+        if block_height < since || block_height > env::block_height() {
+            return None;
+        }
+        if !matches!(self.total_supply.max(), Some(last) if last >= since) {
+            return Some(current_supply);
+        }
+        self.total_supply
+            .floor_key(&block_height)
+            .and_then(|key| self.total_supply.get(&key))
+    }
+
+    /// Remove the checkpoints of the account older than `block_height`, except the latest
+    /// of them, which keeps the balance at `block_height`.
+    pub fn prune_account(&mut self, account_id: &AccountId, block_height: BlockHeight) {
+        if let Some(mut checkpoints) = self.accounts.get(account_id) {
+            let older = checkpoints
+                .iter()
+                .filter(|checkpoint| checkpoint.block_height < block_height)
+                .count();
+            if older > 1 {
+                checkpoints.drain(..older - 1);
+                self.accounts.insert(account_id, &checkpoints);
+            }
+        }
+    }
+
+    /// Remove the total supply checkpoints older than `block_height`, except the latest of them.
+    pub fn prune_total_supply(&mut self, block_height: BlockHeight) {
+        let older: Vec<BlockHeight> = self
+            .total_supply
+            .range((Bound::Unbounded, Bound::Excluded(block_height)))
+            .map(|(key, _)| key)
+            .collect();
+        if let Some((_, pruned)) = older.split_last() {
+            for key in pruned {
+                self.total_supply.remove(key);
+            }
+        }
+    }
+}
+
+/// Checkpoints are valid if they were recorded since the tracking was enabled.
+fn is_valid(checkpoints: &[Checkpoint], since: BlockHeight) -> bool {
+    matches!(checkpoints.last(), Some(last) if last.block_height >= since)
+}
+
+#[ext_contract(ext_balance_checkpoints)]
+pub trait BalanceCheckpointManagement {
+    /// Returns the balance of the account at the end of the block, or `None`
+    /// if the account wasn't tracked at the block or its checkpoints were pruned or dropped
+    /// over the limit of retained checkpoints.
+    fn ft_balance_at(&self, account_id: AccountId, block_height: U64) -> Option<U128>;
+
+    /// Returns the total supply at the end of the block, or `None` if all accounts
+    /// weren't tracked at the block or the checkpoints were pruned.
+    fn ft_total_supply_at(&self, block_height: U64) -> Option<U128>;
+
+    /// Turn on recording the checkpoints of all accounts and the total supply.
+    fn set_checkpoint_tracking(&mut self, enabled: bool);
+
+    /// Turn on recording the checkpoints of the account.
+    fn set_account_checkpoint_tracking(&mut self, account_id: AccountId, enabled: bool);
+
+    /// Returns the block height the history of the account starts at, if the account is tracked.
+    fn get_checkpoint_tracking_since(&self, account_id: AccountId) -> Option<U64>;
+
+    /// Remove the checkpoints older than `block_height` of the total supply and the accounts.
+    /// The balances at `block_height` and later are kept.
+    fn prune_checkpoints(&mut self, block_height: U64, account_ids: Vec<AccountId>);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn set_block_height(block_height: BlockHeight) {
+        testing_env!(VMContextBuilder::new().block_index(block_height).build());
+    }
+
+    #[test]
+    fn test_account_checkpoints() {
+        set_block_height(10);
+        let alice: AccountId = "alice".parse().unwrap();
+        let mut checkpoints = BalanceCheckpoints::new(b"a".to_vec(), b"t".to_vec(), b"s".to_vec());
+        checkpoints.set_track_account(&alice, true, 100);
+
+        set_block_height(20);
+        let since = checkpoints.tracking_since(&alice).unwrap();
+        checkpoints.record_balance(&alice, since, 100, 50, 10);
+        checkpoints.record_balance(&alice, since, 50, 70, 10);
+        set_block_height(30);
+        checkpoints.record_balance(&alice, since, 70, 0, 10);
+
+        assert_eq!(checkpoints.balance_at(&alice, 0, 9), None);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 15), Some(100));
+        assert_eq!(checkpoints.balance_at(&alice, 0, 20), Some(70));
+        assert_eq!(checkpoints.balance_at(&alice, 0, 30), Some(0));
+
+        checkpoints.prune_account(&alice, 25);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 15), None);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 25), Some(70));
+
+        checkpoints.set_track_account(&alice, false, 0);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 25), None);
+    }
+
+    #[test]
+    fn test_account_checkpoints_limit() {
+        set_block_height(10);
+        let alice: AccountId = "alice".parse().unwrap();
+        let mut checkpoints = BalanceCheckpoints::new(b"a".to_vec(), b"t".to_vec(), b"s".to_vec());
+        checkpoints.set_track_account(&alice, true, 0);
+        let since = checkpoints.tracking_since(&alice).unwrap();
+        let max_checkpoints = max_account_checkpoints(240);
+        assert_eq!(max_checkpoints, 10);
+        assert_eq!(max_account_checkpoints(0), MIN_ACCOUNT_CHECKPOINTS);
+        let last_block = 10 + max_checkpoints as BlockHeight;
+        for block_height in 11..=last_block {
+            set_block_height(block_height);
+            checkpoints.record_balance(
+                &alice,
+                since,
+                Balance::from(block_height - 1),
+                Balance::from(block_height),
+                max_checkpoints,
+            );
+        }
+
+        assert_eq!(
+            checkpoints.accounts.get(&alice).unwrap().len(),
+            max_checkpoints
+        );
+        assert_eq!(checkpoints.balance_at(&alice, 0, 10), None);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 11), Some(11));
+        assert_eq!(
+            checkpoints.balance_at(&alice, 0, last_block),
+            Some(Balance::from(last_block))
+        );
+    }
+
+    #[test]
+    fn test_track_all() {
+        set_block_height(10);
+        let alice: AccountId = "alice".parse().unwrap();
+        let mut checkpoints = BalanceCheckpoints::new(b"a".to_vec(), b"t".to_vec(), b"s".to_vec());
+        checkpoints.set_track_all(true);
+        set_block_height(20);
+        assert_eq!(checkpoints.balance_at(&alice, 100, 15), Some(100));
+        assert_eq!(checkpoints.total_supply_at(1000, 15), Some(1000));
+        checkpoints.record_balance(&alice, 10, 100, 150, 10);
+        checkpoints.record_total_supply(1000, 1050);
+        assert_eq!(checkpoints.balance_at(&alice, 150, 15), Some(100));
+        assert_eq!(checkpoints.total_supply_at(1050, 15), Some(1000));
+        assert_eq!(checkpoints.total_supply_at(1050, 20), Some(1050));
+
+        // The changes made while the tracking is off are unknown
+        checkpoints.set_track_all(false);
+        assert_eq!(checkpoints.total_supply_at(1050, 20), None);
+        set_block_height(30);
+        checkpoints.set_track_all(true);
+        assert_eq!(checkpoints.balance_at(&alice, 0, 30), Some(0));
+        assert_eq!(checkpoints.total_supply_at(900, 30), Some(900));
+    }
+}
@@ -1,4 +1,23 @@
-use near_sdk::{ext_contract, json_types::U128, AccountId, PromiseOrValue};
+use near_sdk::{
+    ext_contract,
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+    AccountId, PromiseOrValue,
+};
+
+/// Maximum number of accounts in a single batch balance request.
+pub const MAX_BALANCES_OF_ACCOUNTS: usize = 100;
+
+/// Maximum number of transfers in a single batch transfer.
+pub const MAX_TRANSFER_BATCH_SIZE: usize = 100;
+
+/// Balance of the account and whether the account is registered.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountBalanceView {
+    pub balance: U128,
+    pub registered: bool,
+}
 
 #[ext_contract(ext_ft_core)]
 pub trait FungibleTokenCore {
@@ -14,6 +33,15 @@ pub trait FungibleTokenCore {
     /// - `memo` - an optional string field in a free form to associate a memo with this transfer.
     fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
 
+    /// Transfers tokens from the `env::predecessor_account_id` to many receivers at once.
+    /// The transfers are applied atomically: if any of them fails, none is applied.
+    /// Exactly 1 yoctoNEAR must be attached.
+    ///
+    /// Arguments:
+    /// - `transfers` - the non-empty list of at most `MAX_TRANSFER_BATCH_SIZE` receiver IDs,
+    ///   positive amounts and optional memos.
+    fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128, Option<String>)>);
+
     /// Transfers positive `amount` of tokens from the `env::predecessor_account_id` to `receiver_id` account. Then
     /// calls `ft_on_transfer` method on `receiver_id` contract and attaches a callback to resolve this transfer.
     /// `ft_on_transfer` method must return the amount of tokens unused by the receiver contract, the remaining tokens
@@ -51,5 +79,15 @@ pub trait FungibleTokenCore {
     /// Returns the balance of the account. If the account doesn't exist must returns `"0"`.
     fn ft_balance_of(&self, account_id: AccountId) -> U128;
 
+    /// Returns the balances of the accounts in the same order. Unknown accounts have zero balance.
+    /// At most `MAX_BALANCES_OF_ACCOUNTS` accounts can be requested at once.
+    fn ft_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<U128>;
+
+    /// Same as `ft_balances_of`, but also reports whether each account is registered.
+    fn ft_balances_with_registration_of(
+        &self,
+        account_ids: Vec<AccountId>,
+    ) -> Vec<AccountBalanceView>;
+
     fn ft_total_eth_supply_on_near(&self) -> U128;
 }
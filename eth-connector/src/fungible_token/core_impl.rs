@@ -1,20 +1,27 @@
 use super::{
-    core::FungibleTokenCore,
+    checkpoints::{max_account_checkpoints, BalanceCheckpoints},
+    core::{
+        AccountBalanceView, FungibleTokenCore, MAX_BALANCES_OF_ACCOUNTS, MAX_TRANSFER_BATCH_SIZE,
+    },
     events::{FtBurn, FtTransfer},
+    registration::RegistrationPolicy,
     resolver::FungibleTokenResolver,
+    storage_deposits::StorageDeposits,
 };
-use crate::{errors::ERR_ACCOUNTS_COUNTER_OVERFLOW, SdkUnwrap};
+use crate::{errors::ERR_ACCOUNTS_COUNTER_OVERFLOW, panic_err, SdkUnwrap};
 use aurora_engine_types::types::{NEP141Wei, ZERO_NEP141_WEI};
 
 use crate::errors;
 use crate::fungible_token::engine::EngineFungibleToken;
 
 use near_sdk::{
+    assert_one_yocto,
     borsh::{self, BorshDeserialize, BorshSerialize},
     collections::LookupMap,
     env,
     json_types::U128,
-    AccountId, IntoStorageKey, PromiseOrValue, PromiseResult, StorageUsage,
+    require, AccountId, Balance, IntoStorageKey, Promise, PromiseOrValue, PromiseResult,
+    StorageUsage,
 };
 
 /// Implementation of a FungibleToken standard.
@@ -42,10 +49,24 @@ pub struct FungibleToken {
 
     /// Used proofs
     pub used_proofs: LookupMap<String, bool>,
+
+    /// Historical balances of the tracked accounts
+    pub checkpoints: BalanceCheckpoints,
+
+    /// How unknown receivers are registered
+    pub registration_policy: RegistrationPolicy,
+
+    /// Storage deposits of the accounts and their pending transfer calls
+    pub storage_deposits: StorageDeposits,
 }
 
 impl FungibleToken {
-    pub fn new<S>(prefix_eth: S, prefix_proof: S) -> Self
+    pub fn new<S>(
+        prefix_eth: S,
+        prefix_proof: S,
+        checkpoints: BalanceCheckpoints,
+        storage_deposits: StorageDeposits,
+    ) -> Self
     where
         S: IntoStorageKey,
     {
@@ -55,6 +76,9 @@ impl FungibleToken {
             total_eth_supply_on_near: NEP141Wei::default(),
             statistics_aurora_accounts_counter: 0,
             used_proofs: LookupMap::new(prefix_proof),
+            checkpoints,
+            registration_policy: RegistrationPolicy::AutoRegister,
+            storage_deposits,
         }
     }
 
@@ -84,11 +108,71 @@ impl FungibleToken {
         crate::log!("Mint {} nETH tokens for: {}", amount, owner_id);
 
         if self.get_account_eth_balance(&owner_id).is_none() {
+            if self.registration_policy != RegistrationPolicy::AutoRegister {
+                return Err(error::DepositError::NotRegistered);
+            }
             self.accounts_insert(&owner_id, ZERO_NEP141_WEI);
         }
         self.internal_deposit_eth_to_near(&owner_id, amount)
     }
 
+    /// Check if the account should be registered before receiving tokens.
+    pub fn is_registration_required(&self, account_id: &AccountId) -> bool {
+        self.registration_policy != RegistrationPolicy::AutoRegister
+            && !self.accounts_eth.contains_key(account_id)
+    }
+
+    /// Check the deposit attached to the transfer. Exactly 1 yoctoNEAR is required,
+    /// unless the attached deposit pays for the registration of the receivers.
+    pub fn assert_transfer_deposit(&self) {
+        if self.registration_policy == RegistrationPolicy::ChargeSender {
+            require!(
+                env::attached_deposit() > 0,
+                "Requires attached deposit of at least 1 yoctoNEAR"
+            );
+        } else {
+            assert_one_yocto();
+        }
+    }
+
+    /// Register the unknown receivers according to the registration policy.
+    fn register_receivers<'a>(
+        &mut self,
+        receivers: impl Iterator<Item = &'a AccountId>,
+    ) -> Result<(), error::TransferError> {
+        let initial_storage_usage = env::storage_usage();
+        for receiver_id in receivers {
+            if !self.accounts_eth.contains_key(receiver_id) {
+                if self.registration_policy == RegistrationPolicy::RequireStorageDeposit {
+                    return Err(error::TransferError::ReceiverNotRegistered);
+                }
+                self.accounts_insert(receiver_id, ZERO_NEP141_WEI);
+            }
+        }
+        // The attached deposit should cover the storage of all new accounts. The transfers
+        // without attached deposit are made by the contract itself, which pays the storage.
+        if self.registration_policy == RegistrationPolicy::ChargeSender
+            && env::attached_deposit() > 0
+            && env::attached_deposit() < storage_cost_since(initial_storage_usage)
+        {
+            return Err(error::TransferError::InsufficientRegistrationDeposit);
+        }
+        Ok(())
+    }
+
+    /// Refund the deposit attached to the transfer above the cost of the storage used
+    /// since `initial_storage_usage`. Should be called once by the transfer entrypoints
+    /// checked with `assert_transfer_deposit`.
+    pub fn refund_transfer_deposit(&self, initial_storage_usage: StorageUsage) {
+        if self.registration_policy == RegistrationPolicy::ChargeSender {
+            let refund =
+                env::attached_deposit().saturating_sub(storage_cost_since(initial_storage_usage));
+            if refund > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+        }
+    }
+
     /// Internal ETH deposit to NEAR - nETH (NEP-141)
     pub fn internal_deposit_eth_to_near(
         &mut self,
@@ -103,10 +187,11 @@ impl FungibleToken {
             .ok_or(error::DepositError::BalanceOverflow)?;
 
         self.accounts_insert(account_id, new_balance);
-        self.total_eth_supply_on_near = self
+        let total_supply = self
             .total_eth_supply_on_near
             .checked_add(amount)
             .ok_or(error::DepositError::TotalSupplyOverflow)?;
+        self.set_total_supply(total_supply);
         Ok(())
     }
 
@@ -123,10 +208,43 @@ impl FungibleToken {
             .checked_sub(amount)
             .ok_or(error::WithdrawError::InsufficientFunds)?;
         self.accounts_insert(account_id, new_balance);
-        self.total_eth_supply_on_near = self
+        let total_supply = self
             .total_eth_supply_on_near
             .checked_sub(amount)
             .ok_or(error::WithdrawError::TotalSupplyUnderflow)?;
+        self.set_total_supply(total_supply);
+        Ok(())
+    }
+
+    /// Lock tokens of the account in the contract state. The total supply is not changed.
+    pub fn internal_lock_eth(
+        &mut self,
+        account_id: &AccountId,
+        amount: NEP141Wei,
+    ) -> Result<(), error::WithdrawError> {
+        let balance = self
+            .get_account_eth_balance(account_id)
+            .unwrap_or(ZERO_NEP141_WEI);
+        let new_balance = balance
+            .checked_sub(amount)
+            .ok_or(error::WithdrawError::InsufficientFunds)?;
+        self.accounts_insert(account_id, new_balance);
+        Ok(())
+    }
+
+    /// Return locked tokens to the account.
+    pub fn internal_unlock_eth(
+        &mut self,
+        account_id: &AccountId,
+        amount: NEP141Wei,
+    ) -> Result<(), error::DepositError> {
+        let balance = self
+            .get_account_eth_balance(account_id)
+            .unwrap_or(ZERO_NEP141_WEI);
+        let new_balance = balance
+            .checked_add(amount)
+            .ok_or(error::DepositError::BalanceOverflow)?;
+        self.accounts_insert(account_id, new_balance);
         Ok(())
     }
 
@@ -140,9 +258,35 @@ impl FungibleToken {
                 .ok_or(ERR_ACCOUNTS_COUNTER_OVERFLOW)
                 .sdk_unwrap();
         }
+        self.record_checkpoint(account_id, amount);
         self.accounts_eth.insert(account_id, &amount);
     }
 
+    /// Record the new balance if the account is tracked. Should be called before
+    /// the balance is updated.
+    fn record_checkpoint(&mut self, account_id: &AccountId, amount: NEP141Wei) {
+        if let Some(since) = self.checkpoints.tracking_since(account_id) {
+            let old_balance = self
+                .get_account_eth_balance(account_id)
+                .unwrap_or(ZERO_NEP141_WEI);
+            self.checkpoints.record_balance(
+                account_id,
+                since,
+                old_balance.as_u128(),
+                amount.as_u128(),
+                max_account_checkpoints(self.account_storage_usage),
+            );
+        }
+    }
+
+    pub fn set_total_supply(&mut self, total_supply: NEP141Wei) {
+        self.checkpoints.record_total_supply(
+            self.total_eth_supply_on_near.as_u128(),
+            total_supply.as_u128(),
+        );
+        self.total_eth_supply_on_near = total_supply;
+    }
+
     /// Remove account
     pub fn accounts_remove(&mut self, account_id: &AccountId) {
         if self.accounts_eth.contains_key(account_id) {
@@ -150,6 +294,7 @@ impl FungibleToken {
                 .statistics_aurora_accounts_counter
                 .checked_sub(1)
                 .unwrap_or(self.statistics_aurora_accounts_counter);
+            self.record_checkpoint(account_id, ZERO_NEP141_WEI);
             self.accounts_eth.remove(account_id);
         }
     }
@@ -169,13 +314,10 @@ impl FungibleToken {
             return Err(error::TransferError::ZeroAmount);
         }
 
-        // Check is account receiver_id exist
-        if !self.accounts_eth.contains_key(receiver_id) {
-            // Register receiver_id account with 0 balance. We need it because
-            // when we retire to get the balance of `receiver_id` it will fail
-            // if it does not exist.
-            self.accounts_insert(receiver_id, ZERO_NEP141_WEI);
-        }
+        // Register receiver_id account with 0 balance. We need it because
+        // when we retire to get the balance of `receiver_id` it will fail
+        // if it does not exist.
+        self.register_receivers(std::iter::once(receiver_id))?;
         self.internal_withdraw_eth_from_near(sender_id, amount)?;
         self.internal_deposit_eth_to_near(receiver_id, amount)?;
 
@@ -195,6 +337,65 @@ impl FungibleToken {
         Ok(())
     }
 
+    /// Transfer tokens from the sender to many receivers. The sender balance is checked
+    /// once against the total amount and a single transfer event is emitted.
+    pub fn internal_transfer_batch(
+        &mut self,
+        sender_id: &AccountId,
+        transfers: &[(AccountId, NEP141Wei, Option<String>)],
+    ) -> Result<(), error::TransferError> {
+        if transfers.is_empty() {
+            return Err(error::TransferError::EmptyBatch);
+        }
+        if transfers.len() > MAX_TRANSFER_BATCH_SIZE {
+            return Err(error::TransferError::TooManyTransfers);
+        }
+        let mut total_amount = ZERO_NEP141_WEI;
+        for (receiver_id, amount, _) in transfers {
+            if sender_id == receiver_id {
+                return Err(error::TransferError::SelfTransfer);
+            }
+            if *amount == ZERO_NEP141_WEI {
+                return Err(error::TransferError::ZeroAmount);
+            }
+            total_amount = total_amount
+                .checked_add(*amount)
+                .ok_or(error::TransferError::BalanceOverflow)?;
+        }
+
+        self.register_receivers(transfers.iter().map(|(receiver_id, _, _)| receiver_id))?;
+        // The total supply isn't changed, so the tokens are moved between the balances directly
+        self.internal_lock_eth(sender_id, total_amount)
+            .map_err(|_| error::TransferError::InsufficientFunds)?;
+        for (receiver_id, amount, _) in transfers {
+            self.internal_unlock_eth(receiver_id, *amount)
+                .map_err(|_| error::TransferError::BalanceOverflow)?;
+        }
+        crate::log!(
+            "Transfer {} to {} receivers from {}",
+            total_amount,
+            transfers.len(),
+            sender_id
+        );
+
+        let amounts: Vec<U128> = transfers
+            .iter()
+            .map(|(_, amount, _)| U128(amount.as_u128()))
+            .collect();
+        let events: Vec<FtTransfer> = transfers
+            .iter()
+            .zip(amounts.iter())
+            .map(|((receiver_id, _, memo), amount)| FtTransfer {
+                old_owner_id: sender_id,
+                new_owner_id: receiver_id,
+                amount,
+                memo: memo.as_deref(),
+            })
+            .collect();
+        FtTransfer::emit_many(&events);
+        Ok(())
+    }
+
     /// Balance of nETH (ETH on NEAR token)
     pub fn get_account_eth_balance(&self, account_id: &AccountId) -> Option<NEP141Wei> {
         self.accounts_eth.get(account_id)
@@ -206,6 +407,10 @@ impl FungibleTokenCore for FungibleToken {
         self.engine_ft_transfer(env::predecessor_account_id(), receiver_id, amount, memo)
     }
 
+    fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128, Option<String>)>) {
+        self.engine_ft_transfer_batch(env::predecessor_account_id(), transfers)
+    }
+
     fn ft_transfer_call(
         &mut self,
         receiver_id: AccountId,
@@ -230,6 +435,32 @@ impl FungibleTokenCore for FungibleToken {
         self.total_eth_supply_on_near.as_u128().into()
     }
 
+    fn ft_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<U128> {
+        self.ft_balances_with_registration_of(account_ids)
+            .into_iter()
+            .map(|view| view.balance)
+            .collect()
+    }
+
+    fn ft_balances_with_registration_of(
+        &self,
+        account_ids: Vec<AccountId>,
+    ) -> Vec<AccountBalanceView> {
+        if account_ids.len() > MAX_BALANCES_OF_ACCOUNTS {
+            panic_err(errors::ERR_TOO_MANY_ACCOUNTS);
+        }
+        account_ids
+            .iter()
+            .map(|account_id| {
+                let balance = self.get_account_eth_balance(account_id);
+                AccountBalanceView {
+                    balance: balance.unwrap_or(ZERO_NEP141_WEI).as_u128().into(),
+                    registered: balance.is_some(),
+                }
+            })
+            .collect()
+    }
+
     fn ft_balance_of(&self, account_id: AccountId) -> U128 {
         self.get_account_eth_balance(&account_id)
             .unwrap_or(ZERO_NEP141_WEI)
@@ -248,6 +479,11 @@ impl FungibleToken {
         receiver_id: &AccountId,
         amount: NEP141Wei,
     ) -> (NEP141Wei, NEP141Wei) {
+        self.storage_deposits.finish_transfer_call(sender_id);
+        if sender_id != receiver_id {
+            self.storage_deposits.finish_transfer_call(receiver_id);
+        }
+
         // Get the unused amount from the `ft_on_transfer` call result.
         let unused_amount = match env::promise_result(0) {
             PromiseResult::NotReady => env::abort(),
@@ -309,11 +545,12 @@ impl FungibleToken {
                     return (used_amount, ZERO_NEP141_WEI);
                 } else {
                     // Sender's account was deleted, so we need to burn tokens.
-                    self.total_eth_supply_on_near = self
+                    let total_supply = self
                         .total_eth_supply_on_near
                         .checked_sub(refund_amount)
                         .ok_or(errors::ERR_TOTAL_SUPPLY_OVERFLOW)
                         .sdk_unwrap();
+                    self.set_total_supply(total_supply);
                     crate::log!("The account of the sender {}  was deleted", sender_id);
                     FtBurn {
                         owner_id: receiver_id,
@@ -343,11 +580,19 @@ impl FungibleTokenResolver for FungibleToken {
     }
 }
 
+/// Cost of the storage used since `initial_storage_usage`.
+fn storage_cost_since(initial_storage_usage: StorageUsage) -> Balance {
+    Balance::from(env::storage_usage().saturating_sub(initial_storage_usage))
+        * env::storage_byte_cost()
+}
+
 pub mod error {
     use crate::deposit_event::error::ParseOnTransferMessageError;
+    use crate::engine_registry::error::EngineError;
     use crate::errors::{
-        ERR_BALANCE_OVERFLOW, ERR_NOT_ENOUGH_BALANCE, ERR_NOT_ENOUGH_BALANCE_FOR_FEE,
-        ERR_PROOF_EXIST, ERR_SENDER_EQUALS_RECEIVER, ERR_TOTAL_SUPPLY_OVERFLOW,
+        ERR_ACCOUNT_NOT_REGISTERED, ERR_ATTACHED_DEPOSIT_NOT_ENOUGH, ERR_BALANCE_OVERFLOW,
+        ERR_EMPTY_BATCH, ERR_NOT_ENOUGH_BALANCE, ERR_NOT_ENOUGH_BALANCE_FOR_FEE, ERR_PROOF_EXIST,
+        ERR_SENDER_EQUALS_RECEIVER, ERR_TOO_MANY_TRANSFERS, ERR_TOTAL_SUPPLY_OVERFLOW,
         ERR_TOTAL_SUPPLY_UNDERFLOW, ERR_WRONG_EVENT_ADDRESS, ERR_ZERO_AMOUNT,
     };
     use aurora_engine_types::types::balance::error::BalanceOverflowError;
@@ -365,6 +610,7 @@ pub mod error {
     pub enum DepositError {
         TotalSupplyOverflow,
         BalanceOverflow,
+        NotRegistered,
     }
 
     impl AsRef<[u8]> for DepositError {
@@ -372,6 +618,7 @@ pub mod error {
             match self {
                 Self::TotalSupplyOverflow => ERR_TOTAL_SUPPLY_OVERFLOW.as_bytes(),
                 Self::BalanceOverflow => ERR_BALANCE_OVERFLOW,
+                Self::NotRegistered => ERR_ACCOUNT_NOT_REGISTERED,
             }
         }
     }
@@ -381,6 +628,7 @@ pub mod error {
             match err {
                 DepositError::BalanceOverflow => Self::BalanceOverflow,
                 DepositError::TotalSupplyOverflow => Self::TotalSupplyOverflow,
+                DepositError::NotRegistered => Self::ReceiverNotRegistered,
             }
         }
     }
@@ -389,6 +637,7 @@ pub mod error {
         ProofParseFailed,
         CustodianAddressMismatch,
         InsufficientAmountForFee,
+        InvalidEngine(EngineError),
     }
 
     impl AsRef<[u8]> for FtDepositError {
@@ -397,6 +646,7 @@ pub mod error {
                 Self::ProofParseFailed => ERR_FAILED_PARSE.as_bytes(),
                 Self::CustodianAddressMismatch => ERR_WRONG_EVENT_ADDRESS,
                 Self::InsufficientAmountForFee => ERR_NOT_ENOUGH_BALANCE_FOR_FEE.as_bytes(),
+                Self::InvalidEngine(e) => e.as_ref(),
             }
         }
     }
@@ -480,6 +730,10 @@ pub mod error {
         BalanceOverflow,
         ZeroAmount,
         SelfTransfer,
+        ReceiverNotRegistered,
+        InsufficientRegistrationDeposit,
+        EmptyBatch,
+        TooManyTransfers,
     }
 
     impl AsRef<[u8]> for TransferError {
@@ -491,6 +745,10 @@ pub mod error {
                 Self::BalanceOverflow => ERR_BALANCE_OVERFLOW,
                 Self::ZeroAmount => ERR_ZERO_AMOUNT,
                 Self::SelfTransfer => ERR_SENDER_EQUALS_RECEIVER,
+                Self::ReceiverNotRegistered => ERR_ACCOUNT_NOT_REGISTERED,
+                Self::InsufficientRegistrationDeposit => ERR_ATTACHED_DEPOSIT_NOT_ENOUGH,
+                Self::EmptyBatch => ERR_EMPTY_BATCH,
+                Self::TooManyTransfers => ERR_TOO_MANY_TRANSFERS,
             }
         }
     }
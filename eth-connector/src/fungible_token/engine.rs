@@ -11,6 +11,12 @@ pub trait EngineFungibleToken {
         memo: Option<String>,
     );
 
+    fn engine_ft_transfer_batch(
+        &mut self,
+        sender_id: AccountId,
+        transfers: Vec<(AccountId, U128, Option<String>)>,
+    );
+
     fn engine_ft_transfer_call(
         &mut self,
         sender_id: AccountId,
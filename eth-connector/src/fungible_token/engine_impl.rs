@@ -12,7 +12,7 @@ use near_sdk::json_types::U128;
 use near_sdk::{assert_one_yocto, env, require, AccountId, Balance, Gas, Promise, PromiseOrValue};
 
 const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
-const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+pub const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
 
 impl EngineFungibleToken for FungibleToken {
     fn engine_ft_transfer(
@@ -22,10 +22,12 @@ impl EngineFungibleToken for FungibleToken {
         amount: U128,
         memo: Option<String>,
     ) {
-        assert_one_yocto();
+        self.assert_transfer_deposit();
+        let initial_storage_usage = env::storage_usage();
         let amount: Balance = amount.into();
         self.internal_transfer_eth_on_near(&sender_id, &receiver_id, NEP141Wei::new(amount), &memo)
             .sdk_unwrap();
+        self.refund_transfer_deposit(initial_storage_usage);
         crate::log!(
             "Transfer amount {} to {} success with memo: {:?}",
             amount,
@@ -34,6 +36,22 @@ impl EngineFungibleToken for FungibleToken {
         );
     }
 
+    fn engine_ft_transfer_batch(
+        &mut self,
+        sender_id: AccountId,
+        transfers: Vec<(AccountId, U128, Option<String>)>,
+    ) {
+        self.assert_transfer_deposit();
+        let initial_storage_usage = env::storage_usage();
+        let transfers: Vec<(AccountId, NEP141Wei, Option<String>)> = transfers
+            .into_iter()
+            .map(|(receiver_id, amount, memo)| (receiver_id, NEP141Wei::new(amount.0), memo))
+            .collect();
+        self.internal_transfer_batch(&sender_id, &transfers)
+            .sdk_unwrap();
+        self.refund_transfer_deposit(initial_storage_usage);
+    }
+
     fn engine_ft_transfer_call(
         &mut self,
         sender_id: AccountId,
@@ -46,48 +64,20 @@ impl EngineFungibleToken for FungibleToken {
             env::prepaid_gas() > GAS_FOR_FT_TRANSFER_CALL,
             ERR_MORE_GAS_REQUIRED
         );
-        crate::log!(
-            "Transfer call from {} to {} amount {}",
-            sender_id,
-            receiver_id,
-            amount.0,
-        );
-
-        // Verify message data before `ft_on_transfer` call to avoid verification panics
-        // It's allowed empty message if `receiver_id =! current_account_id`
-        if sender_id == receiver_id {
-            let message_data = FtTransferMessageData::parse_on_transfer_message(&msg).sdk_unwrap();
-            // Check is transfer amount > fee
-            if message_data.fee.as_u128() >= amount.0 {
-                panic_err(error::FtTransferCallError::InsufficientAmountForFee);
-            }
-        }
-
-        // Special case for Aurora transfer itself - we shouldn't transfer
-        if sender_id != receiver_id {
-            self.internal_transfer_eth_on_near(
-                &sender_id,
-                &receiver_id,
-                NEP141Wei::new(amount.0),
-                &memo,
-            )
-            .sdk_unwrap();
-        }
         let receiver_gas = env::prepaid_gas()
             .0
             .checked_sub(GAS_FOR_FT_TRANSFER_CALL.0)
             .ok_or(ERR_PREPAID_GAS_OVERFLOW)
             .sdk_unwrap();
-        // Initiating receiver's call and the callback
-        ext_ft_receiver::ext(receiver_id.clone())
-            .with_static_gas(receiver_gas.into())
-            .ft_on_transfer(sender_id.clone(), amount, msg)
-            .then(
-                ext_ft_resolver::ext(env::current_account_id())
-                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
-                    .ft_resolve_transfer(sender_id, receiver_id, amount),
-            )
-            .into()
+        self.internal_ft_transfer_call(
+            sender_id,
+            receiver_id,
+            amount,
+            memo,
+            msg,
+            receiver_gas.into(),
+        )
+        .into()
     }
 
     #[allow(unused_variables)]
@@ -115,6 +105,8 @@ impl EngineFungibleToken for FungibleToken {
             }
 
             self.accounts_insert(&account_id, ZERO_NEP141_WEI);
+            self.storage_deposits
+                .record_deposit(&account_id, min_balance);
             let refund = amount - min_balance;
             crate::log!(
                 "Storage deposit {:?} for account {} with refund {:?}",
@@ -155,3 +147,60 @@ impl EngineFungibleToken for FungibleToken {
         self.internal_storage_unregister(sender_id, force).is_some()
     }
 }
+
+impl FungibleToken {
+    /// Transfer tokens to the receiver and call its `ft_on_transfer` with `receiver_gas`.
+    /// The caller is responsible for reserving the gas for the rest of the call.
+    pub fn internal_ft_transfer_call(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+        receiver_gas: Gas,
+    ) -> Promise {
+        crate::log!(
+            "Transfer call from {} to {} amount {}",
+            sender_id,
+            receiver_id,
+            amount.0,
+        );
+
+        // Verify message data before `ft_on_transfer` call to avoid verification panics
+        // It's allowed empty message if `receiver_id =! current_account_id`
+        if sender_id == receiver_id {
+            let message_data = FtTransferMessageData::parse_on_transfer_message(&msg).sdk_unwrap();
+            // Check is transfer amount > fee
+            if message_data.fee.as_u128() >= amount.0 {
+                panic_err(error::FtTransferCallError::InsufficientAmountForFee);
+            }
+        }
+
+        // Special case for Aurora transfer itself - we shouldn't transfer
+        if sender_id != receiver_id {
+            self.internal_transfer_eth_on_near(
+                &sender_id,
+                &receiver_id,
+                NEP141Wei::new(amount.0),
+                &memo,
+            )
+            .sdk_unwrap();
+        }
+        // The accounts can't be unregistered until the transfer is resolved
+        self.storage_deposits.start_transfer_call(&sender_id);
+        if sender_id != receiver_id {
+            self.storage_deposits.start_transfer_call(&receiver_id);
+        }
+
+        // Initiating receiver's call and the callback
+        ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(receiver_gas)
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                ext_ft_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+    }
+}
@@ -7,6 +7,7 @@ use serde::Serialize;
 #[serde(rename_all = "snake_case")]
 pub(crate) enum NearEvent<'a> {
     Nep141(super::events::Nep141Event<'a>),
+    EthConnector(crate::connector_events::EthConnectorEvent<'a>),
 }
 
 impl<'a> NearEvent<'a> {
@@ -1,3 +1,4 @@
+pub mod checkpoints;
 pub mod core;
 pub mod core_impl;
 pub mod engine;
@@ -6,8 +7,10 @@ pub mod event;
 pub mod events;
 pub mod metadata;
 pub mod receiver;
+pub mod registration;
 pub mod resolver;
 pub mod statistic;
 pub mod statistic_impl;
+pub mod storage_deposits;
 pub mod storage_impl;
 pub mod storage_management;
@@ -0,0 +1,29 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    ext_contract,
+    serde::{Deserialize, Serialize},
+};
+
+/// How transfers and mints treat receivers without storage registration.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum RegistrationPolicy {
+    /// Unknown receivers are registered for free.
+    AutoRegister,
+    /// Receivers should be registered with `storage_deposit` in advance.
+    RequireStorageDeposit,
+    /// The deposit attached to the transfer pays for the registration of unknown receivers.
+    /// The rest of the attached deposit is refunded to the caller.
+    /// Mints have no attached deposit, so their receivers should be registered in advance.
+    ChargeSender,
+}
+
+#[ext_contract(ext_registration_policy)]
+pub trait RegistrationPolicyManagement {
+    fn set_registration_policy(&mut self, policy: RegistrationPolicy);
+
+    fn get_registration_policy(&self) -> RegistrationPolicy;
+}
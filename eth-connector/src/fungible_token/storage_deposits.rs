@@ -0,0 +1,107 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    AccountId, Balance, IntoStorageKey,
+};
+
+/// Storage deposits paid by the registered accounts and the `ft_transfer_call`
+/// awaiting `ft_resolve_transfer` for each account.
+///
+/// Only `storage_deposit` is recorded as paid. Nothing is refunded on unregistration to
+/// the accounts without a recorded deposit: the accounts registered for free, by the sender
+/// of the transfer, or before the deposits were recorded.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct StorageDeposits {
+    pub deposits: LookupMap<AccountId, Balance>,
+    pub pending_transfer_calls: LookupMap<AccountId, u64>,
+}
+
+impl StorageDeposits {
+    pub fn new<S: IntoStorageKey>(prefix_deposits: S, prefix_pending_transfer_calls: S) -> Self {
+        Self {
+            deposits: LookupMap::new(prefix_deposits),
+            pending_transfer_calls: LookupMap::new(prefix_pending_transfer_calls),
+        }
+    }
+
+    pub fn deposit_of(&self, account_id: &AccountId) -> Balance {
+        self.deposits.get(account_id).unwrap_or(0)
+    }
+
+    pub fn record_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        if amount > 0 {
+            let deposit = self.deposit_of(account_id).saturating_add(amount);
+            self.deposits.insert(account_id, &deposit);
+        }
+    }
+
+    /// Remove the deposit of the account and return its amount.
+    pub fn take_deposit(&mut self, account_id: &AccountId) -> Balance {
+        self.deposits.remove(account_id).unwrap_or(0)
+    }
+
+    pub fn pending_transfer_calls_of(&self, account_id: &AccountId) -> u64 {
+        self.pending_transfer_calls.get(account_id).unwrap_or(0)
+    }
+
+    pub fn start_transfer_call(&mut self, account_id: &AccountId) {
+        let pending = self.pending_transfer_calls_of(account_id).saturating_add(1);
+        self.pending_transfer_calls.insert(account_id, &pending);
+    }
+
+    /// Reset the counter of the account, e.g. when `ft_resolve_transfer` has failed
+    /// and the counter is stuck. Returns the cleared number of transfer calls.
+    pub fn clear_transfer_calls(&mut self, account_id: &AccountId) -> u64 {
+        self.pending_transfer_calls.remove(account_id).unwrap_or(0)
+    }
+
+    /// Called on `ft_resolve_transfer`. The transfer calls started before the counter
+    /// was introduced aren't counted, so the counter never goes below zero.
+    pub fn finish_transfer_call(&mut self, account_id: &AccountId) {
+        match self.pending_transfer_calls_of(account_id) {
+            0 => {}
+            1 => {
+                self.pending_transfer_calls.remove(account_id);
+            }
+            pending => {
+                self.pending_transfer_calls
+                    .insert(account_id, &(pending - 1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_storage_deposits() {
+        testing_env!(VMContextBuilder::new().build());
+        let alice: AccountId = "alice".parse().unwrap();
+        let mut storage_deposits = StorageDeposits::new(b"d".to_vec(), b"p".to_vec());
+
+        assert_eq!(storage_deposits.take_deposit(&alice), 0);
+        storage_deposits.record_deposit(&alice, 0);
+        assert!(!storage_deposits.deposits.contains_key(&alice));
+        storage_deposits.record_deposit(&alice, 100);
+        assert_eq!(storage_deposits.deposit_of(&alice), 100);
+        assert_eq!(storage_deposits.take_deposit(&alice), 100);
+        assert_eq!(storage_deposits.deposit_of(&alice), 0);
+
+        storage_deposits.start_transfer_call(&alice);
+        storage_deposits.start_transfer_call(&alice);
+        storage_deposits.finish_transfer_call(&alice);
+        assert_eq!(storage_deposits.pending_transfer_calls_of(&alice), 1);
+        storage_deposits.finish_transfer_call(&alice);
+        storage_deposits.finish_transfer_call(&alice);
+        assert_eq!(storage_deposits.pending_transfer_calls_of(&alice), 0);
+        assert!(!storage_deposits.pending_transfer_calls.contains_key(&alice));
+
+        storage_deposits.start_transfer_call(&alice);
+        assert_eq!(storage_deposits.clear_transfer_calls(&alice), 1);
+        assert_eq!(storage_deposits.pending_transfer_calls_of(&alice), 0);
+    }
+}
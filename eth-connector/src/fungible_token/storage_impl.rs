@@ -6,7 +6,8 @@ use near_sdk::{assert_one_yocto, env, json_types::U128, AccountId, Balance, Prom
 
 impl FungibleToken {
     /// Internal method that returns the Account ID and the balance in case the account was
-    /// unregistered.
+    /// unregistered. The storage deposit paid by the account is refunded together with
+    /// the attached yoctoNEAR.
     pub fn internal_storage_unregister(
         &mut self,
         account_id: AccountId,
@@ -16,10 +17,14 @@ impl FungibleToken {
 
         let force = force.unwrap_or(false);
         if let Some(balance) = self.get_account_eth_balance(&account_id) {
+            if self.storage_deposits.pending_transfer_calls_of(&account_id) > 0 {
+                panic_err(error::StorageFundingError::PendingTransferCalls);
+            }
             if balance == NEP141Wei::new(0) || force {
                 self.accounts_remove(&account_id);
-                self.total_eth_supply_on_near -= balance;
-                Promise::new(account_id.clone()).transfer(self.storage_balance_bounds().min.0 + 1);
+                self.set_total_supply(self.total_eth_supply_on_near - balance);
+                let refund = self.storage_deposits.take_deposit(&account_id) + 1;
+                Promise::new(account_id.clone()).transfer(refund);
                 Some((account_id, balance))
             } else {
                 panic_err(error::StorageFundingError::UnRegisterPositiveBalance);
@@ -91,6 +96,7 @@ pub mod error {
         NoAvailableBalance,
         InsufficientDeposit,
         UnRegisterPositiveBalance,
+        PendingTransferCalls,
     }
 
     impl AsRef<[u8]> for StorageFundingError {
@@ -102,6 +108,7 @@ pub mod error {
                 Self::UnRegisterPositiveBalance => {
                     errors::ERR_FAILED_UNREGISTER_ACCOUNT_POSITIVE_BALANCE
                 }
+                Self::PendingTransferCalls => errors::ERR_PENDING_TRANSFER_CALLS,
             }
         }
     }
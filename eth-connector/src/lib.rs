@@ -1,40 +1,99 @@
-use crate::admin_controlled::{AdminControlled, PausedMask, PAUSE_WITHDRAW, UNPAUSE_ALL};
-use crate::connector::{ConnectorDeposit, ConnectorFundsFinish, ConnectorWithdraw};
+use crate::admin_controlled::{
+    error::AdminControlledError, AdminControlled, PausedMask, PAUSE_DEPOSIT, PAUSE_WITHDRAW,
+    UNPAUSE_ALL,
+};
+use crate::allowance::{Allowances, FungibleTokenAllowance};
+use crate::connector::{
+    ext_funds_finish, ConnectorDeposit, ConnectorFundsFinish, ConnectorWithdraw,
+};
+use crate::connector_events::WithdrawCall;
 use crate::connector_impl::{
-    EthConnector, FinishDepositCallArgs, TransferCallCallArgs, WithdrawResult,
+    DepositSimulation, EthConnector, FinishDepositCallArgs, TransferCallCallArgs,
+    WithdrawBatchStatus, WithdrawResult, GAS_FOR_DEPOSIT_CALL_FALLBACK,
+};
+use crate::delayed_withdraw::{
+    ext_delayed_withdraw, DelayedWithdraw, QueuedWithdrawal, QueuedWithdrawalView,
+    WithdrawDelayConfig, WithdrawOutcome, WithdrawalQueue, GAS_FOR_WITHDRAW_QUEUED,
+};
+use crate::denylist::{Denylist, DenylistManagement};
+use crate::deposit_event::{DepositedEvent, FtTransferMessageData, TokenMessageData};
+use crate::deposit_history::{
+    DepositHistory, DepositHistoryConfig, DepositHistoryView, DepositRecord, DepositRecordView,
+    DEFAULT_DEPOSITS_OF_LIMIT,
 };
+use crate::engine_registry::{
+    EngineInfo, EngineManagement, EngineRegistry, EngineView, DEFAULT_ENGINES_LIMIT,
+};
+use crate::fee_policy::{FeePolicy, FeePolicyManagement};
+use crate::fungible_token::checkpoints::{BalanceCheckpointManagement, BalanceCheckpoints};
 use crate::fungible_token::engine::EngineFungibleToken;
 use crate::fungible_token::{
-    core::FungibleTokenCore,
+    core::{AccountBalanceView, FungibleTokenCore},
     core_impl::FungibleToken,
+    engine_impl::GAS_FOR_FT_TRANSFER_CALL,
+    events::FtBurn,
     metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider},
+    registration::{RegistrationPolicy, RegistrationPolicyManagement},
     resolver::FungibleTokenResolver,
     statistic::FungibleTokeStatistic,
+    storage_deposits::StorageDeposits,
     storage_management::{StorageBalance, StorageBalanceBounds, StorageManagement},
 };
+use crate::meta_transfer::{MetaTransfer, MetaTransfers, TransferAuthorization};
+use crate::migration::{LegacyEthConnectorContract, STATE_VERSION};
+use crate::mint_limit::{MintLimitManagement, MintLimiter, MintLimits, MintWindow};
+use crate::pending_deposit::{
+    HoldReason, PendingDeposit, PendingDepositManagement, PendingDepositView, PendingDeposits,
+    DEFAULT_PENDING_DEPOSITS_LIMIT,
+};
 use crate::proof::Proof;
+use crate::relayer_registry::{RelayerInfo, RelayerManagement, RelayerRegistry};
 use crate::types::{panic_err, SdkUnwrap};
+use crate::withdraw_fee::{WithdrawFeeManagement, WithdrawFeeSchedule};
+use crate::withdraw_receiver::{
+    ext_withdraw_receiver, ext_withdraw_resolver, WithdrawCallResolver,
+    GAS_FOR_RESOLVE_WITHDRAW_CALL, GAS_FOR_WITHDRAW_CALL,
+};
+use crate::withdrawal_ledger::{
+    WithdrawalLedger, WithdrawalLedgerView, WithdrawalRecord, WithdrawalRecordView,
+    DEFAULT_WITHDRAWALS_OF_LIMIT,
+};
 use aurora_engine_types::types::{Address, NEP141Wei, ZERO_NEP141_WEI};
 use near_sdk::{
     assert_one_yocto,
     borsh::{self, BorshDeserialize, BorshSerialize},
     collections::LazyOption,
     env,
-    json_types::{U128, U64},
-    near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
+    json_types::{Base64VecU8, U128, U64},
+    near_bindgen, require, AccountId, Balance, BorshStorageKey, IntoStorageKey, PanicOnDefault,
+    Promise, PromiseOrValue, PromiseResult, PublicKey,
 };
 
 pub mod admin_controlled;
+pub mod allowance;
 pub mod connector;
+pub mod connector_events;
 pub mod connector_impl;
+pub mod delayed_withdraw;
+pub mod denylist;
 pub mod deposit_event;
+pub mod deposit_history;
+pub mod engine_registry;
 pub mod errors;
+pub mod fee_policy;
 pub mod fungible_token;
 pub mod log_entry;
+pub mod meta_transfer;
 pub mod migration;
+pub mod mint_limit;
+pub mod pending_deposit;
 pub mod proof;
+pub mod relayer_registry;
 pub mod types;
 pub mod wei;
+pub mod withdraw_fee;
+pub mod withdraw_receiver;
+pub mod withdrawal_ledger;
 
 /// Eth-connector contract data. It's stored in the storage.
 /// Contains:
@@ -47,6 +106,14 @@ pub struct EthConnectorContract {
     connector: EthConnector,
     ft: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
+    relayers: RelayerRegistry,
+    pending_deposits: PendingDeposits,
+    denylist: Denylist,
+    deposit_history: DepositHistory,
+    withdrawals: WithdrawalLedger,
+    withdrawal_queue: WithdrawalQueue,
+    allowances: Allowances,
+    meta_transfers: MetaTransfers,
 }
 
 #[derive(BorshSerialize, BorshStorageKey)]
@@ -54,6 +121,27 @@ enum StorageKey {
     FungibleTokenEth = 0x1,
     Proof = 0x2,
     Metadata = 0x3,
+    StateVersion = 0x4,
+    Relayers = 0x5,
+    PendingDeposits = 0x6,
+    DeniedAccounts = 0x7,
+    SuspiciousAddresses = 0x8,
+    DepositRecords = 0x9,
+    AccountDeposits = 0xa,
+    AddressDeposits = 0xb,
+    Withdrawals = 0xc,
+    AccountWithdrawals = 0xd,
+    AccountWithdrawalCounters = 0xe,
+    QueuedWithdrawals = 0xf,
+    Engines = 0x10,
+    Allowances = 0x11,
+    TransferNonces = 0x12,
+    SigningKeys = 0x13,
+    BalanceCheckpoints = 0x14,
+    CheckpointTrackedAccounts = 0x15,
+    TotalSupplyCheckpoints = 0x16,
+    StorageDeposits = 0x17,
+    PendingTransferCalls = 0x18,
 }
 
 #[near_bindgen]
@@ -75,17 +163,127 @@ impl EthConnectorContract {
             paused_mask,
             eth_custodian_address: Address::decode(&eth_custodian_address).unwrap(),
             account_with_access_right,
+            fee_policy: FeePolicy::default(),
+            mint_limiter: MintLimiter::default(),
+            withdraw_fee: WithdrawFeeSchedule::default(),
+            engines: EngineRegistry::new(StorageKey::Engines),
         };
         let owner_id = env::current_account_id();
-        let mut this = Self {
-            ft: FungibleToken::new(StorageKey::FungibleTokenEth, StorageKey::Proof),
-            connector: connector_data,
-            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
-        };
+        let mut this = Self::from_parts(
+            connector_data,
+            Self::new_fungible_token(),
+            LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+        );
         this.ft.accounts_insert(&owner_id, ZERO_NEP141_WEI);
+        Self::write_state_version();
+        this
+    }
+
+    /// Migrate the state stored with the layout before the state versioning.
+    /// The added data is initialized as in `new`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate_state() -> Self {
+        if Self::state_version().is_some() {
+            env::panic_str(errors::ERR_STATE_ALREADY_MIGRATED);
+        }
+        let legacy: LegacyEthConnectorContract =
+            env::state_read().unwrap_or_else(|| env::panic_str(errors::ERR_STATE_NOT_INITIALIZED));
+        let connector = EthConnector {
+            prover_account: legacy.connector.prover_account,
+            eth_custodian_address: legacy.connector.eth_custodian_address,
+            paused_mask: legacy.connector.paused_mask,
+            account_with_access_right: legacy.connector.account_with_access_right,
+            fee_policy: FeePolicy::default(),
+            mint_limiter: MintLimiter::default(),
+            withdraw_fee: WithdrawFeeSchedule::default(),
+            engines: EngineRegistry::new(StorageKey::Engines),
+        };
+        let mut ft = Self::new_fungible_token();
+        ft.accounts_eth = legacy.ft.accounts_eth;
+        ft.total_eth_supply_on_near = legacy.ft.total_eth_supply_on_near;
+        ft.account_storage_usage = legacy.ft.account_storage_usage;
+        ft.statistics_aurora_accounts_counter = legacy.ft.statistics_aurora_accounts_counter;
+        ft.used_proofs = legacy.ft.used_proofs;
+        let this = Self::from_parts(connector, ft, legacy.metadata);
+        Self::write_state_version();
+        crate::log!("Migrated the state to version {}", STATE_VERSION);
         this
     }
 
+    /// Returns the version of the state layout, 0 for the state before the versioning.
+    pub fn get_state_version(&self) -> u32 {
+        Self::state_version().unwrap_or(0)
+    }
+
+    fn state_version() -> Option<u32> {
+        env::storage_read(&StorageKey::StateVersion.into_storage_key()).map(|value| {
+            u32::try_from_slice(&value)
+                .map_err(|_| errors::ERR_BORSH_DESERIALIZE)
+                .sdk_unwrap()
+        })
+    }
+
+    fn write_state_version() {
+        env::storage_write(
+            &StorageKey::StateVersion.into_storage_key(),
+            &STATE_VERSION.to_le_bytes(),
+        );
+    }
+
+    /// Parse the hex Ethereum address with the optional `0x` prefix.
+    fn parse_address(address: &str) -> Address {
+        let address = address.strip_prefix("0x").unwrap_or(address);
+        Address::decode(address)
+            .map_err(|_| errors::ERR_PARSE_ADDRESS)
+            .sdk_unwrap()
+    }
+
+    fn new_fungible_token() -> FungibleToken {
+        FungibleToken::new(
+            StorageKey::FungibleTokenEth,
+            StorageKey::Proof,
+            BalanceCheckpoints::new(
+                StorageKey::BalanceCheckpoints,
+                StorageKey::CheckpointTrackedAccounts,
+                StorageKey::TotalSupplyCheckpoints,
+            ),
+            StorageDeposits::new(
+                StorageKey::StorageDeposits,
+                StorageKey::PendingTransferCalls,
+            ),
+        )
+    }
+
+    /// Contract with the connector, the token and the metadata, and empty rest of the data.
+    fn from_parts(
+        connector: EthConnector,
+        ft: FungibleToken,
+        metadata: LazyOption<FungibleTokenMetadata>,
+    ) -> Self {
+        Self {
+            connector,
+            ft,
+            metadata,
+            relayers: RelayerRegistry::new(StorageKey::Relayers),
+            pending_deposits: PendingDeposits::new(StorageKey::PendingDeposits),
+            denylist: Denylist::new(StorageKey::DeniedAccounts, StorageKey::SuspiciousAddresses),
+            deposit_history: DepositHistory::new(
+                StorageKey::DepositRecords,
+                StorageKey::AccountDeposits,
+                StorageKey::AddressDeposits,
+            ),
+            withdrawals: WithdrawalLedger::new(
+                StorageKey::Withdrawals,
+                StorageKey::AccountWithdrawals,
+                StorageKey::AccountWithdrawalCounters,
+            ),
+            withdrawal_queue: WithdrawalQueue::new(StorageKey::QueuedWithdrawals),
+            allowances: Allowances::new(StorageKey::Allowances),
+            meta_transfers: MetaTransfers::new(StorageKey::TransferNonces, StorageKey::SigningKeys),
+        }
+    }
+
     #[cfg_attr(not(feature = "log"), allow(unused_variables))]
     fn on_account_closed(&self, _account_id: AccountId, _balance: NEP141Wei) {
         crate::log!("Closed @{} with {}", _account_id, _balance);
@@ -96,11 +294,474 @@ impl EthConnectorContract {
         crate::log!("Account @{} burned {}", account_id, amount);
     }
 
+    /// Refuse deposits from unbonding relayers, and from unregistered relayers while
+    /// allowlist mode is on.
+    /// The owner is always allowed.
+    fn assert_relayer(&self) {
+        let predecessor_account_id = env::predecessor_account_id();
+        if predecessor_account_id != env::current_account_id() {
+            self.relayers
+                .assert_relayer(&predecessor_account_id)
+                .sdk_unwrap();
+        }
+    }
+
+    /// Check access to the deposit entry points. While allowlist mode is on, the registered
+    /// relayers submit deposits without the access right.
+    fn assert_deposit_access(&self) {
+        if !self.relayers.allowlist_mode {
+            self.assert_access_right().sdk_unwrap();
+        }
+        self.assert_relayer();
+    }
+
+    /// The relayer fee of the deposits to NEAR accounts is minted to the relayer, so it
+    /// should be registered unless the registration policy registers it on mint.
+    fn assert_relayer_registered(&self, proof: &Proof) {
+        if !self
+            .ft
+            .is_registration_required(&env::predecessor_account_id())
+        {
+            return;
+        }
+        let event = DepositedEvent::from_log_entry_data(&proof.log_entry_data).sdk_unwrap();
+        let is_near_deposit = matches!(
+            event.token_message_data,
+            TokenMessageData::Near(_) | TokenMessageData::NearCall { .. }
+        );
+        let has_relayer_fee = matches!(
+            self.connector.fee_policy.apply(event.amount, event.fee),
+            Ok(fee) if fee.relayer_fee.as_u128() > 0
+        );
+        if is_near_deposit && has_relayer_fee {
+            panic_err(errors::ERR_ACCOUNT_NOT_REGISTERED);
+        }
+    }
+
+    /// Check that the deposit recipients, including the relayer and the treasury paid
+    /// from the fee, can receive tokens under the registration policy.
+    fn is_recipient_registered(&self, deposit_call: &FinishDepositCallArgs) -> bool {
+        let near_call_receiver_id = deposit_call
+            .near_call
+            .as_ref()
+            .map(|near_call| &near_call.receiver_id);
+        // The relayer fee of the deposits to Eth accounts is minted by the engine
+        let relayer_id = (deposit_call.msg.is_none() && deposit_call.fee.as_u128() > 0)
+            .then_some(&deposit_call.relayer_id);
+        let treasury_id = deposit_call
+            .protocol_fee
+            .as_ref()
+            .map(|protocol_fee| &protocol_fee.treasury_id);
+        !std::iter::once(&deposit_call.new_owner_id)
+            .chain(near_call_receiver_id)
+            .chain(relayer_id)
+            .chain(treasury_id)
+            .any(|receiver_id| self.ft.is_registration_required(receiver_id))
+    }
+
+    /// Mint tokens for the verified deposit. The proof should be already recorded.
+    fn internal_finish_deposit(
+        &mut self,
+        deposit_call: FinishDepositCallArgs,
+    ) -> PromiseOrValue<Option<U128>> {
+        self.relayers
+            .record_deposit(&deposit_call.relayer_id, deposit_call.fee);
+        let mut record = DepositRecord {
+            proof_key: deposit_call.proof_key.clone(),
+            sender: deposit_call.sender,
+            recipient: deposit_call.new_owner_id.clone(),
+            eth_recipient: None,
+            amount: deposit_call.mint_amount(),
+            fee: deposit_call.fee,
+            relayer_id: deposit_call.relayer_id.clone(),
+            block_height: env::block_height(),
+        };
+
+        // Mint tokens to recipient minus fee
+        let promise_or_value = if let Some(msg) = deposit_call.msg {
+            // Mint - calculate new balances
+            self.ft
+                .mint_eth_on_near(deposit_call.new_owner_id, deposit_call.amount)
+                .sdk_unwrap();
+
+            let data: TransferCallCallArgs = TransferCallCallArgs::try_from_slice(&msg)
+                .map_err(|_| crate::errors::ERR_BORSH_DESERIALIZE)
+                .sdk_unwrap();
+            let message = FtTransferMessageData::parse_on_transfer_message(&data.msg).ok();
+            record.recipient = data.receiver_id.clone();
+            record.eth_recipient = message.as_ref().map(|message| message.recipient);
+            self.deposit_history.record(&record);
+            // The amount refunded after the failed EVM call is credited to the plain address
+            let fallback_msg =
+                message.and_then(|mut message| message.evm_call.take().map(|_| message.encode()));
+            let promise = match fallback_msg {
+                Some(fallback_msg) => self.internal_deposit_call_with_fallback(data, fallback_msg),
+                None => self.ft.ft_transfer_call(
+                    data.receiver_id,
+                    data.amount.as_u128().into(),
+                    data.memo,
+                    data.msg,
+                ),
+            };
+            match promise {
+                PromiseOrValue::Promise(p) => PromiseOrValue::Promise(p),
+                PromiseOrValue::Value(v) => PromiseOrValue::Value(Some(v)),
+            }
+        } else {
+            let protocol_fee = deposit_call
+                .protocol_fee
+                .as_ref()
+                .map_or(0, |protocol_fee| protocol_fee.amount.as_u128());
+            let amount = deposit_call.amount
+                - NEP141Wei::new(deposit_call.fee.as_u128())
+                - NEP141Wei::new(protocol_fee);
+            // Mint - calculate new balances
+            self.ft
+                .mint_eth_on_near(deposit_call.new_owner_id.clone(), amount)
+                .sdk_unwrap();
+            if deposit_call.fee.as_u128() > 0 {
+                self.ft
+                    .mint_eth_on_near(
+                        deposit_call.relayer_id,
+                        NEP141Wei::new(deposit_call.fee.as_u128()),
+                    )
+                    .sdk_unwrap();
+            }
+            self.deposit_history.record(&record);
+            // Transfer the minted tokens on behalf of the owner
+            match deposit_call.near_call {
+                Some(near_call) => {
+                    match self.ft.engine_ft_transfer_call(
+                        deposit_call.new_owner_id,
+                        near_call.receiver_id,
+                        amount.as_u128().into(),
+                        None,
+                        near_call.msg,
+                    ) {
+                        PromiseOrValue::Promise(p) => PromiseOrValue::Promise(p),
+                        PromiseOrValue::Value(v) => PromiseOrValue::Value(Some(v)),
+                    }
+                }
+                None => PromiseOrValue::Value(None),
+            }
+        };
+
+        // Mint the protocol share of the fee to the treasury
+        if let Some(protocol_fee) = deposit_call.protocol_fee {
+            self.ft
+                .mint_eth_on_near(
+                    protocol_fee.treasury_id,
+                    NEP141Wei::new(protocol_fee.amount.as_u128()),
+                )
+                .sdk_unwrap();
+        }
+        promise_or_value
+    }
+
+    /// Transfer the deposit with the EVM call to the engine and reserve the gas
+    /// for the fallback to the plain address.
+    fn internal_deposit_call_with_fallback(
+        &mut self,
+        data: TransferCallCallArgs,
+        fallback_msg: String,
+    ) -> PromiseOrValue<U128> {
+        let reserved_gas = GAS_FOR_FT_TRANSFER_CALL.0 + GAS_FOR_DEPOSIT_CALL_FALLBACK.0;
+        require!(
+            env::prepaid_gas().0 > reserved_gas,
+            errors::ERR_MORE_GAS_REQUIRED
+        );
+        let receiver_gas = env::prepaid_gas().0 - reserved_gas;
+        let amount: U128 = data.amount.as_u128().into();
+        self.ft
+            .internal_ft_transfer_call(
+                env::current_account_id(),
+                data.receiver_id.clone(),
+                amount,
+                data.memo,
+                data.msg,
+                receiver_gas.into(),
+            )
+            .then(
+                ext_funds_finish::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_DEPOSIT_CALL_FALLBACK)
+                    .finish_deposit_call_fallback(data.receiver_id, amount, fallback_msg),
+            )
+            .into()
+    }
+
+    /// Check access to the engine entry points. Registered engines have access
+    /// in addition to the account with access right and the contract itself.
+    fn assert_engine_access(&self) -> Result<(), AdminControlledError> {
+        if self
+            .connector
+            .engines
+            .is_registered(&env::predecessor_account_id())
+        {
+            return Ok(());
+        }
+        self.assert_access_right()
+    }
+
+    /// Whether the predecessor is a registered engine without the access right.
+    /// Such engine acts only for itself and for the accounts which gave it an allowance.
+    fn is_scoped_engine(&self) -> bool {
+        self.assert_access_right().is_err()
+            && self
+                .connector
+                .engines
+                .is_registered(&env::predecessor_account_id())
+    }
+
+    /// Check that a registered engine manages its own storage only.
+    fn assert_engine_storage_owner(&self, sender_id: &AccountId) {
+        if self.is_scoped_engine() && *sender_id != env::predecessor_account_id() {
+            panic_err(AdminControlledError::AccessRight);
+        }
+    }
+
+    /// Draw down the allowance given by the sender to the calling engine. The engine's
+    /// own funds are not limited. The account with access right is limited only
+    /// while the engine allowance mode is on.
+    fn spend_engine_allowance(&mut self, sender_id: &AccountId, amount: Balance) {
+        if self.is_engine_allowance_required(sender_id) {
+            self.allowances
+                .spend(sender_id, &env::predecessor_account_id(), amount)
+                .sdk_unwrap();
+        }
+    }
+
+    fn is_engine_allowance_required(&self, sender_id: &AccountId) -> bool {
+        *sender_id != env::predecessor_account_id()
+            && (self.allowances.engine_allowance_mode || self.is_scoped_engine())
+    }
+
+    /// Check if withdrawals are paused. The owner is always allowed.
+    fn assert_withdraw_not_paused(&self) {
+        let predecessor_account_id = env::predecessor_account_id();
+        let current_account_id = env::current_account_id();
+        // Check is current account id is owner
+        let is_owner = current_account_id == predecessor_account_id;
+        // Check is current flow paused. If it's owner just skip asserrion.
+        self.assert_not_paused(PAUSE_WITHDRAW, is_owner)
+            .map_err(|_| "WithdrawErrorPaused")
+            .sdk_unwrap();
+    }
+
+    /// Withdraw tokens of the sender and emit the burn event.
+    fn internal_withdraw(
+        &mut self,
+        sender_id: &AccountId,
+        recipient_address: Address,
+        amount: NEP141Wei,
+        engine_id: Option<&AccountId>,
+    ) -> WithdrawOutcome {
+        let outcome =
+            self.internal_process_withdraw(sender_id, recipient_address, amount, engine_id);
+        if let WithdrawOutcome::Burned(result) = &outcome {
+            Self::emit_withdraw_burns(&[(sender_id, result)]);
+        }
+        outcome
+    }
+
+    /// Return the result of the burned withdrawal. The queued withdrawal returns
+    /// the `withdraw_queued` callback, which outcome has no `WithdrawResult`.
+    fn withdraw_outcome_result(outcome: WithdrawOutcome) -> PromiseOrValue<WithdrawResult> {
+        match outcome {
+            WithdrawOutcome::Burned(result) => PromiseOrValue::Value(result),
+            WithdrawOutcome::Queued { nonce, .. } => PromiseOrValue::Promise(
+                ext_delayed_withdraw::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_WITHDRAW_QUEUED)
+                    .withdraw_queued(nonce.into()),
+            ),
+        }
+    }
+
+    /// Withdraw tokens of the sender. Withdrawals above the delay threshold are queued:
+    /// the tokens are locked with the fee fixed until `finalize_withdraw` burns them.
+    /// `engine_id` is the engine which made the withdrawal on behalf of the sender.
+    fn internal_process_withdraw(
+        &mut self,
+        sender_id: &AccountId,
+        recipient_address: Address,
+        amount: NEP141Wei,
+        engine_id: Option<&AccountId>,
+    ) -> WithdrawOutcome {
+        self.assert_withdraw_not_paused();
+        if let Some(engine_id) = engine_id {
+            self.connector
+                .engines
+                .assert_not_paused(engine_id, PAUSE_WITHDRAW)
+                .sdk_unwrap();
+            self.connector
+                .engines
+                .record_withdraw(engine_id, amount.as_u128());
+        }
+        let nonce = self.withdrawals.next_nonce();
+        // Split off the withdraw fee
+        let fee = self.connector.withdraw_fee.fee_for(amount).sdk_unwrap();
+        if !self.withdrawal_queue.config.is_delayed(amount) {
+            return WithdrawOutcome::Burned(self.internal_burn_withdrawal(
+                sender_id,
+                recipient_address,
+                amount,
+                fee,
+                nonce,
+            ));
+        }
+
+        self.ft.internal_lock_eth(sender_id, amount).sdk_unwrap();
+        let unlock_block = env::block_height() + self.withdrawal_queue.config.delay_blocks.0;
+        self.withdrawal_queue.push(&QueuedWithdrawal {
+            nonce,
+            sender_id: sender_id.clone(),
+            recipient_address,
+            amount,
+            fee,
+            engine_id: engine_id.cloned(),
+            unlock_block,
+        });
+        crate::log!(
+            "Withdrawal {} of {} is queued until block {}",
+            nonce,
+            amount,
+            unlock_block
+        );
+        WithdrawOutcome::Queued {
+            nonce,
+            unlock_block,
+        }
+    }
+
+    /// Check that the withdrawal of the sender made by the engine would succeed.
+    fn check_withdraw(
+        &self,
+        sender_id: &AccountId,
+        amount: NEP141Wei,
+        engine_id: Option<&AccountId>,
+    ) -> Result<(), &[u8]> {
+        if engine_id.is_some()
+            && self.is_engine_allowance_required(sender_id)
+            && self
+                .allowances
+                .get(sender_id, &env::predecessor_account_id())
+                < amount.as_u128()
+        {
+            return Err(errors::ERR_INSUFFICIENT_ALLOWANCE);
+        }
+        if let Some(engine) = engine_id.and_then(|engine_id| self.connector.engines.get(engine_id))
+        {
+            if engine.paused_mask & PAUSE_WITHDRAW != 0 {
+                return Err(errors::ERR_ENGINE_PAUSED);
+            }
+        }
+        let fee = self
+            .connector
+            .withdraw_fee
+            .fee_for(amount)
+            .map_err(|_| errors::ERR_WITHDRAW_FEE_TOO_HIGH.as_ref())?;
+        // The fee is transferred to the collector, which should be able to receive it
+        if let Some(collector_id) = self.connector.withdraw_fee.collector_id.as_ref() {
+            if fee > ZERO_NEP141_WEI
+                && collector_id != sender_id
+                && self.ft.is_registration_required(collector_id)
+            {
+                return Err(errors::ERR_FEE_COLLECTOR_NOT_REGISTERED);
+            }
+        }
+        let balance = self
+            .ft
+            .get_account_eth_balance(sender_id)
+            .unwrap_or(ZERO_NEP141_WEI);
+        if balance < amount {
+            return Err(errors::ERR_NOT_ENOUGH_BALANCE);
+        }
+        Ok(())
+    }
+
+    /// Emit one burn event for the burned withdrawals. Zero burns are skipped.
+    fn emit_withdraw_burns(withdrawals: &[(&AccountId, &WithdrawResult)]) {
+        let burned: Vec<(&AccountId, U128)> = withdrawals
+            .iter()
+            .filter(|(_, result)| result.amount > ZERO_NEP141_WEI)
+            .map(|(sender_id, result)| (*sender_id, U128(result.amount.as_u128())))
+            .collect();
+        if burned.is_empty() {
+            return;
+        }
+        let events: Vec<FtBurn> = burned
+            .iter()
+            .map(|(owner_id, amount)| FtBurn {
+                owner_id,
+                amount,
+                memo: Some("withdraw"),
+            })
+            .collect();
+        FtBurn::emit_many(&events);
+    }
+
+    /// Charge the withdraw fee, burn tokens of the sender and record the withdrawal.
+    fn internal_burn_withdrawal(
+        &mut self,
+        sender_id: &AccountId,
+        recipient_address: Address,
+        amount: NEP141Wei,
+        fee: NEP141Wei,
+        nonce: u64,
+    ) -> WithdrawResult {
+        // The collector is always set for the non-zero fee
+        if let Some(collector_id) = self.connector.withdraw_fee.collector_id.clone() {
+            if fee > ZERO_NEP141_WEI && &collector_id != sender_id {
+                self.ft
+                    .internal_transfer_eth_on_near(
+                        sender_id,
+                        &collector_id,
+                        fee,
+                        &Some("withdraw fee".to_string()),
+                    )
+                    .sdk_unwrap();
+            }
+        }
+        let amount = amount - fee;
+        // Burn tokens to recipient
+        self.ft
+            .internal_withdraw_eth_from_near(sender_id, amount)
+            .sdk_unwrap();
+        self.withdrawals.record(&WithdrawalRecord {
+            nonce,
+            sender_id: sender_id.clone(),
+            recipient_address,
+            amount,
+            fee,
+            eth_custodian_address: self.connector.eth_custodian_address,
+            block_height: env::block_height(),
+        });
+        WithdrawResult {
+            recipient_id: recipient_address,
+            amount,
+            eth_custodian_address: self.connector.eth_custodian_address,
+            nonce,
+            fee,
+        }
+    }
+
     #[result_serializer(borsh)]
     pub fn is_used_proof(&self, #[serializer(borsh)] proof: Proof) -> bool {
         self.ft.is_used_event(&proof.get_key())
     }
 
+    /// Dry-run of the deposit for the proof: parses the event and runs the custodian,
+    /// fee and used-proof checks without calling the prover.
+    /// `relayer_id` defaults to the current account.
+    pub fn simulate_deposit(
+        &self,
+        proof: Proof,
+        relayer_id: Option<AccountId>,
+    ) -> DepositSimulation {
+        let relayer_id = relayer_id.unwrap_or_else(env::current_account_id);
+        self.connector
+            .simulate_deposit(&proof, relayer_id, |key| self.ft.is_used_event(key))
+    }
+
     #[cfg(feature = "integration-test")]
     #[result_serializer(borsh)]
     pub fn verify_log_entry() -> bool {
@@ -111,6 +772,19 @@ impl EthConnectorContract {
     pub fn get_bridge_prover(&self) -> AccountId {
         self.connector.prover_account.clone()
     }
+
+    /// Clear the `ft_transfer_call` counter of the account which blocks the unregistration
+    /// after the failed `ft_resolve_transfer`.
+    #[private]
+    pub fn clear_pending_transfer_calls(&mut self, account_id: AccountId) {
+        #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+        let cleared = self.ft.storage_deposits.clear_transfer_calls(&account_id);
+        crate::log!(
+            "Cleared {} pending transfer calls of {}",
+            cleared,
+            account_id
+        );
+    }
 }
 
 #[near_bindgen]
@@ -123,11 +797,30 @@ impl EngineFungibleToken for EthConnectorContract {
         amount: U128,
         memo: Option<String>,
     ) {
-        self.assert_access_right().sdk_unwrap();
+        self.assert_engine_access().sdk_unwrap();
+        self.spend_engine_allowance(&sender_id, amount.0);
         self.ft
             .engine_ft_transfer(sender_id, receiver_id, amount, memo)
     }
 
+    #[payable]
+    fn engine_ft_transfer_batch(
+        &mut self,
+        sender_id: AccountId,
+        transfers: Vec<(AccountId, U128, Option<String>)>,
+    ) {
+        self.assert_engine_access().sdk_unwrap();
+        let total_amount = transfers
+            .iter()
+            .try_fold(0, |total: Balance, (_, amount, _)| {
+                total.checked_add(amount.0)
+            })
+            .ok_or(errors::ERR_BALANCE_OVERFLOW)
+            .sdk_unwrap();
+        self.spend_engine_allowance(&sender_id, total_amount);
+        self.ft.engine_ft_transfer_batch(sender_id, transfers)
+    }
+
     #[payable]
     fn engine_ft_transfer_call(
         &mut self,
@@ -137,10 +830,15 @@ impl EngineFungibleToken for EthConnectorContract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        self.assert_access_right().sdk_unwrap();
-        assert_one_yocto();
-        self.ft
-            .engine_ft_transfer_call(sender_id, receiver_id, amount, memo, msg)
+        self.assert_engine_access().sdk_unwrap();
+        self.ft.assert_transfer_deposit();
+        let initial_storage_usage = env::storage_usage();
+        self.spend_engine_allowance(&sender_id, amount.0);
+        let promise_or_value =
+            self.ft
+                .engine_ft_transfer_call(sender_id, receiver_id, amount, memo, msg);
+        self.ft.refund_transfer_deposit(initial_storage_usage);
+        promise_or_value
     }
 
     #[payable]
@@ -150,7 +848,7 @@ impl EngineFungibleToken for EthConnectorContract {
         account_id: Option<AccountId>,
         registration_only: Option<bool>,
     ) -> StorageBalance {
-        self.assert_access_right().sdk_unwrap();
+        self.assert_engine_access().sdk_unwrap();
         self.ft
             .engine_storage_deposit(sender_id, account_id, registration_only)
     }
@@ -161,13 +859,15 @@ impl EngineFungibleToken for EthConnectorContract {
         sender_id: AccountId,
         amount: Option<U128>,
     ) -> StorageBalance {
-        self.assert_access_right().sdk_unwrap();
+        self.assert_engine_access().sdk_unwrap();
+        self.assert_engine_storage_owner(&sender_id);
         self.ft.engine_storage_withdraw(sender_id, amount)
     }
 
     #[payable]
     fn engine_storage_unregister(&mut self, sender_id: AccountId, force: Option<bool>) -> bool {
-        self.assert_access_right().sdk_unwrap();
+        self.assert_engine_access().sdk_unwrap();
+        self.assert_engine_storage_owner(&sender_id);
         if let Some((account_id, balance)) = self.ft.internal_storage_unregister(sender_id, force) {
             self.on_account_closed(account_id, balance);
             true
@@ -185,6 +885,12 @@ impl FungibleTokenCore for EthConnectorContract {
         self.ft.ft_transfer(receiver_id, amount, memo)
     }
 
+    #[payable]
+    fn ft_transfer_batch(&mut self, transfers: Vec<(AccountId, U128, Option<String>)>) {
+        self.assert_access_right().sdk_unwrap();
+        self.ft.ft_transfer_batch(transfers)
+    }
+
     #[payable]
     fn ft_transfer_call(
         &mut self,
@@ -194,8 +900,11 @@ impl FungibleTokenCore for EthConnectorContract {
         msg: String,
     ) -> PromiseOrValue<U128> {
         self.assert_access_right().sdk_unwrap();
-        assert_one_yocto();
-        self.ft.ft_transfer_call(receiver_id, amount, memo, msg)
+        self.ft.assert_transfer_deposit();
+        let initial_storage_usage = env::storage_usage();
+        let promise_or_value = self.ft.ft_transfer_call(receiver_id, amount, memo, msg);
+        self.ft.refund_transfer_deposit(initial_storage_usage);
+        promise_or_value
     }
 
     fn ft_total_supply(&self) -> U128 {
@@ -206,6 +915,17 @@ impl FungibleTokenCore for EthConnectorContract {
         self.ft.ft_balance_of(account_id)
     }
 
+    fn ft_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<U128> {
+        self.ft.ft_balances_of(account_ids)
+    }
+
+    fn ft_balances_with_registration_of(
+        &self,
+        account_ids: Vec<AccountId>,
+    ) -> Vec<AccountBalanceView> {
+        self.ft.ft_balances_with_registration_of(account_ids)
+    }
+
     fn ft_total_eth_supply_on_near(&self) -> U128 {
         log!(
             "Total ETH supply on NEAR: {}",
@@ -232,6 +952,14 @@ impl FungibleTokenResolver for EthConnectorContract {
         if burned_amount > ZERO_NEP141_WEI {
             self.on_tokens_burned(sender_id.clone(), burned_amount);
         }
+        // The burned refund never reached the engine
+        let received_amount = used_amount
+            .checked_sub(burned_amount)
+            .unwrap_or(ZERO_NEP141_WEI);
+        self.connector
+            .engines
+            .record_deposit(&receiver_id, received_amount.as_u128())
+            .sdk_unwrap();
         log!(
             "Resolve transfer from {} to {}, used token amount {:?} success",
             sender_id,
@@ -329,35 +1057,162 @@ impl ConnectorWithdraw for EthConnectorContract {
         #[serializer(borsh)] sender_id: AccountId,
         #[serializer(borsh)] recipient_address: Address,
         #[serializer(borsh)] amount: NEP141Wei,
-    ) -> WithdrawResult {
-        self.assert_access_right().sdk_unwrap();
+    ) -> PromiseOrValue<WithdrawResult> {
+        self.assert_engine_access().sdk_unwrap();
         assert_one_yocto();
-        let predecessor_account_id = env::predecessor_account_id();
-        let current_account_id = env::current_account_id();
-        // Check is current account id is owner
-        let is_owner = current_account_id == predecessor_account_id;
-        // Check is current flow paused. If it's owner just skip asserrion.
-        self.assert_not_paused(PAUSE_WITHDRAW, is_owner)
-            .map_err(|_| "WithdrawErrorPaused")
-            .sdk_unwrap();
-        // Burn tokens to recipient
-        self.ft
-            .internal_withdraw_eth_from_near(&sender_id, amount)
-            .sdk_unwrap();
-        WithdrawResult {
-            recipient_id: recipient_address,
+        let engine_id = env::predecessor_account_id();
+        self.spend_engine_allowance(&sender_id, amount.as_u128());
+        let outcome =
+            self.internal_withdraw(&sender_id, recipient_address, amount, Some(&engine_id));
+        Self::withdraw_outcome_result(outcome)
+    }
+
+    #[payable]
+    #[result_serializer(borsh)]
+    fn withdraw_batch(
+        &mut self,
+        #[serializer(borsh)] withdrawals: Vec<(AccountId, Address, NEP141Wei)>,
+        #[serializer(borsh)] isolate_failures: bool,
+    ) -> Vec<WithdrawBatchStatus> {
+        self.assert_engine_access().sdk_unwrap();
+        assert_one_yocto();
+        let engine_id = env::predecessor_account_id();
+        let mut statuses = Vec::with_capacity(withdrawals.len());
+        for (sender_id, recipient_address, amount) in &withdrawals {
+            if isolate_failures {
+                if let Err(err) = self.check_withdraw(sender_id, *amount, Some(&engine_id)) {
+                    let error = String::from_utf8_lossy(err).into_owned();
+                    log!(
+                        "Skip withdrawal of {} from {}: {}",
+                        amount,
+                        sender_id,
+                        error
+                    );
+                    statuses.push(WithdrawBatchStatus::Failed { error });
+                    continue;
+                }
+            }
+            self.spend_engine_allowance(sender_id, amount.as_u128());
+            let status = match self.internal_process_withdraw(
+                sender_id,
+                *recipient_address,
+                *amount,
+                Some(&engine_id),
+            ) {
+                WithdrawOutcome::Burned(result) => WithdrawBatchStatus::Burned(result),
+                WithdrawOutcome::Queued {
+                    nonce,
+                    unlock_block,
+                } => WithdrawBatchStatus::Queued {
+                    nonce,
+                    unlock_block,
+                },
+            };
+            statuses.push(status);
+        }
+        let burned: Vec<(&AccountId, &WithdrawResult)> = withdrawals
+            .iter()
+            .zip(&statuses)
+            .filter_map(|((sender_id, _, _), status)| match status {
+                WithdrawBatchStatus::Burned(result) => Some((sender_id, result)),
+                _ => None,
+            })
+            .collect();
+        Self::emit_withdraw_burns(&burned);
+        statuses
+    }
+
+    #[payable]
+    #[result_serializer(borsh)]
+    fn withdraw_to_eth(
+        &mut self,
+        #[serializer(borsh)] recipient_address: Address,
+        #[serializer(borsh)] amount: NEP141Wei,
+    ) -> PromiseOrValue<WithdrawResult> {
+        assert_one_yocto();
+        let outcome = self.internal_withdraw(
+            &env::predecessor_account_id(),
+            recipient_address,
             amount,
-            eth_custodian_address: self.connector.eth_custodian_address,
+            None,
+        );
+        Self::withdraw_outcome_result(outcome)
+    }
+
+    #[payable]
+    fn withdraw_call(
+        &mut self,
+        #[serializer(borsh)] receiver_id: AccountId,
+        #[serializer(borsh)] recipient_address: Address,
+        #[serializer(borsh)] amount: NEP141Wei,
+        #[serializer(borsh)] msg: String,
+    ) -> Promise {
+        assert_one_yocto();
+        require!(
+            env::prepaid_gas() > GAS_FOR_WITHDRAW_CALL + GAS_FOR_RESOLVE_WITHDRAW_CALL,
+            errors::ERR_MORE_GAS_REQUIRED
+        );
+        let sender_id = env::predecessor_account_id();
+        let result = match self.internal_withdraw(&sender_id, recipient_address, amount, None) {
+            WithdrawOutcome::Burned(result) => result,
+            WithdrawOutcome::Queued { .. } => env::panic_str(errors::ERR_WITHDRAW_DELAYED),
+        };
+        let receiver_gas = env::prepaid_gas()
+            .0
+            .checked_sub(env::used_gas().0)
+            .and_then(|gas| gas.checked_sub(GAS_FOR_WITHDRAW_CALL.0))
+            .and_then(|gas| gas.checked_sub(GAS_FOR_RESOLVE_WITHDRAW_CALL.0))
+            .unwrap_or_else(|| env::panic_str(errors::ERR_PREPAID_GAS_OVERFLOW));
+        // Notify the receiver and resolve the result
+        ext_withdraw_receiver::ext(receiver_id.clone())
+            .with_static_gas(receiver_gas.into())
+            .on_withdraw(sender_id.clone(), (&result).into(), msg)
+            .then(
+                ext_withdraw_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_WITHDRAW_CALL)
+                    .withdraw_call_resolve(sender_id, receiver_id, result),
+            )
+    }
+}
+
+#[near_bindgen]
+impl WithdrawCallResolver for EthConnectorContract {
+    #[private]
+    #[result_serializer(borsh)]
+    fn withdraw_call_resolve(
+        &mut self,
+        #[serializer(borsh)] sender_id: AccountId,
+        #[serializer(borsh)] receiver_id: AccountId,
+        #[serializer(borsh)] result: WithdrawResult,
+    ) -> WithdrawResult {
+        let notified = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        WithdrawCall {
+            sender_id: &sender_id,
+            receiver_id: &receiver_id,
+            recipient_address: &result.recipient_id.encode(),
+            amount: &result.amount.as_u128().into(),
+            fee: &result.fee.as_u128().into(),
+            nonce: &result.nonce.into(),
+            notified,
         }
+        .emit();
+        result
     }
 }
 
 #[near_bindgen]
 impl ConnectorDeposit for EthConnectorContract {
     fn deposit(&mut self, #[serializer(borsh)] raw_proof: Proof) -> Promise {
-        self.assert_access_right().sdk_unwrap();
+        self.assert_deposit_access();
+        self.assert_relayer_registered(&raw_proof);
         self.connector.deposit(raw_proof)
     }
+
+    fn deposit_json(&mut self, proof: Proof) -> Promise {
+        self.assert_deposit_access();
+        self.assert_relayer_registered(&proof);
+        self.connector.deposit_json(proof)
+    }
 }
 
 #[near_bindgen]
@@ -376,46 +1231,673 @@ impl ConnectorFundsFinish for EthConnectorContract {
 
         log!("Finish deposit with the amount: {}", deposit_call.amount);
 
-        // Mint tokens to recipient minus fee
-        if let Some(msg) = deposit_call.msg {
-            // Mint - calculate new balances
-            self.ft
-                .mint_eth_on_near(deposit_call.new_owner_id, deposit_call.amount)
-                .sdk_unwrap();
-            // Store proof only after `mint` calculations
-            self.ft.record_proof(&deposit_call.proof_key).sdk_unwrap();
+        // Store proof before the limits check, so the held deposit can't be submitted again
+        self.ft.record_proof(&deposit_call.proof_key).sdk_unwrap();
 
-            let data: TransferCallCallArgs = TransferCallCallArgs::try_from_slice(&msg)
-                .map_err(|_| crate::errors::ERR_BORSH_DESERIALIZE)
-                .sdk_unwrap();
-            let promise = self.ft.ft_transfer_call(
-                data.receiver_id,
-                data.amount.as_u128().into(),
-                data.memo,
-                data.msg,
+        if let Some(reason) = self.denylist.hold_reason(&deposit_call) {
+            crate::log!("Deposit {} is held: {:?}", deposit_call.proof_key, reason);
+            self.pending_deposits.insert(&PendingDeposit {
+                deposit_call,
+                reason,
+                block_height: env::block_height(),
+            });
+            return PromiseOrValue::Value(None);
+        }
+
+        // Hold the deposit until the recipient is registered
+        if !self.is_recipient_registered(&deposit_call) {
+            crate::log!(
+                "Deposit {} is held: the recipient is not registered",
+                deposit_call.proof_key
             );
-            match promise {
-                PromiseOrValue::Promise(p) => PromiseOrValue::Promise(p),
-                PromiseOrValue::Value(v) => PromiseOrValue::Value(Some(v)),
+            self.pending_deposits.insert(&PendingDeposit {
+                deposit_call,
+                reason: HoldReason::RecipientNotRegistered,
+                block_height: env::block_height(),
+            });
+            return PromiseOrValue::Value(None);
+        }
+
+        let mint_amount = deposit_call.mint_amount().as_u128();
+        match self
+            .connector
+            .mint_limiter
+            .try_mint(mint_amount, env::block_height())
+        {
+            Ok(()) => self.internal_finish_deposit(deposit_call),
+            Err(reason) => {
+                crate::log!("Deposit {} is held: {:?}", deposit_call.proof_key, reason);
+                self.pending_deposits.insert(&PendingDeposit {
+                    deposit_call,
+                    reason,
+                    block_height: env::block_height(),
+                });
+                // A single large deposit is held alone, the exhausted window pauses the deposits
+                if reason == HoldReason::MintWindowLimit {
+                    crate::log!("Mint window limit is reached. Deposits are paused");
+                    let paused_mask = self.connector.get_paused_flags() | PAUSE_DEPOSIT;
+                    self.connector.set_paused_flags(paused_mask);
+                }
+                PromiseOrValue::Value(None)
             }
+        }
+    }
+
+    #[private]
+    fn finish_deposit_call_fallback(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        // The result of `ft_resolve_transfer` is the used amount. If the resolve failed
+        // the refund is unknown and the engine may keep the tokens, so nothing is credited.
+        let used_amount = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<U128>(&value).map_or(amount.0, |used| used.0)
+            }
+            _ => {
+                log!(
+                    "Resolve of the deposit call to {} failed. Nothing is credited to the plain address",
+                    receiver_id
+                );
+                amount.0
+            }
+        };
+        let refunded_amount = amount.0.saturating_sub(used_amount);
+        if refunded_amount == 0 {
+            return PromiseOrValue::Value(amount);
+        }
+        log!(
+            "EVM call of the deposit to {} failed. Credit {} to the plain address",
+            receiver_id,
+            refunded_amount
+        );
+        self.ft.ft_transfer_call(
+            receiver_id,
+            refunded_amount.into(),
+            Some("deposit call fallback".to_string()),
+            msg,
+        )
+    }
+}
+
+#[near_bindgen]
+impl FeePolicyManagement for EthConnectorContract {
+    #[private]
+    fn set_fee_policy(&mut self, policy: FeePolicy) {
+        policy.validate().sdk_unwrap();
+        self.connector.fee_policy = policy;
+    }
+
+    fn get_fee_policy(&self) -> FeePolicy {
+        self.connector.fee_policy.clone()
+    }
+}
+
+#[near_bindgen]
+impl RelayerManagement for EthConnectorContract {
+    #[payable]
+    fn register_relayer(&mut self) -> RelayerInfo {
+        let account_id = env::predecessor_account_id();
+        let stake = env::attached_deposit();
+        let initial_storage_usage = env::storage_usage();
+        let relayer = self.relayers.register(&account_id, stake).sdk_unwrap();
+        // The stake should also cover the storage of the relayer record
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        if relayer.stake.0 < storage_cost {
+            panic_err(errors::ERR_ATTACHED_DEPOSIT_NOT_ENOUGH);
+        }
+        crate::log!("Relayer @{} registered with stake {}", account_id, stake);
+        relayer
+    }
+
+    #[payable]
+    fn unregister_relayer(&mut self) -> U64 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let unbonding_until = self
+            .relayers
+            .unregister(&account_id, env::block_height())
+            .sdk_unwrap();
+        crate::log!(
+            "Relayer @{} unregistered, the stake is locked until block {}",
+            account_id,
+            unbonding_until
+        );
+        unbonding_until.into()
+    }
+
+    #[payable]
+    fn withdraw_relayer_stake(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let relayer = self
+            .relayers
+            .withdraw_stake(&account_id, env::block_height())
+            .sdk_unwrap();
+        Promise::new(account_id.clone()).transfer(relayer.stake.0 + 1);
+        crate::log!(
+            "Relayer @{} withdrew the stake {}",
+            account_id,
+            relayer.stake.0
+        );
+        relayer.stake
+    }
+
+    fn get_relayer(&self, account_id: AccountId) -> Option<RelayerInfo> {
+        self.relayers.get(&account_id)
+    }
+
+    #[private]
+    fn set_relayer_allowlist_mode(&mut self, enabled: bool) {
+        self.relayers.allowlist_mode = enabled;
+    }
+
+    fn is_relayer_allowlist_mode(&self) -> bool {
+        self.relayers.allowlist_mode
+    }
+
+    #[private]
+    fn set_relayer_min_stake(&mut self, min_stake: U128) {
+        self.relayers.min_stake = min_stake.0;
+    }
+
+    fn get_relayer_min_stake(&self) -> U128 {
+        self.relayers.min_stake.into()
+    }
+
+    #[private]
+    fn set_relayer_unbonding_blocks(&mut self, unbonding_blocks: U64) {
+        self.relayers.unbonding_blocks = unbonding_blocks.0;
+    }
+
+    fn get_relayer_unbonding_blocks(&self) -> U64 {
+        self.relayers.unbonding_blocks.into()
+    }
+}
+
+#[near_bindgen]
+impl DenylistManagement for EthConnectorContract {
+    #[private]
+    fn add_denied_account(&mut self, account_id: AccountId) {
+        self.denylist.accounts.insert(&account_id);
+    }
+
+    #[private]
+    fn remove_denied_account(&mut self, account_id: AccountId) {
+        self.denylist.accounts.remove(&account_id);
+    }
+
+    fn is_account_denied(&self, account_id: AccountId) -> bool {
+        self.denylist.accounts.contains(&account_id)
+    }
+
+    #[private]
+    fn add_suspicious_address(&mut self, address: String) {
+        let address = Self::parse_address(&address);
+        self.denylist.suspicious_addresses.insert(&address);
+    }
+
+    #[private]
+    fn remove_suspicious_address(&mut self, address: String) {
+        let address = Self::parse_address(&address);
+        self.denylist.suspicious_addresses.remove(&address);
+    }
+
+    fn is_address_suspicious(&self, address: String) -> bool {
+        let address = Self::parse_address(&address);
+        self.denylist.suspicious_addresses.contains(&address)
+    }
+}
+
+#[near_bindgen]
+impl EngineManagement for EthConnectorContract {
+    #[private]
+    fn register_engine(&mut self, engine_id: AccountId) {
+        self.connector.engines.register(&engine_id).sdk_unwrap();
+    }
+
+    #[private]
+    fn unregister_engine(&mut self, engine_id: AccountId) -> EngineInfo {
+        self.connector.engines.unregister(&engine_id).sdk_unwrap()
+    }
+
+    #[private]
+    fn set_engine_paused_flags(&mut self, engine_id: AccountId, paused_mask: PausedMask) {
+        self.connector
+            .engines
+            .set_paused_flags(&engine_id, paused_mask)
+            .sdk_unwrap();
+    }
+
+    fn get_engine(&self, engine_id: AccountId) -> Option<EngineInfo> {
+        self.connector.engines.get(&engine_id)
+    }
+
+    fn get_engines(&self, from_index: Option<U64>, limit: Option<U64>) -> Vec<EngineView> {
+        self.connector.engines.page(
+            from_index.map_or(0, |index| index.0),
+            limit.map_or(DEFAULT_ENGINES_LIMIT, |limit| limit.0),
+        )
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenAllowance for EthConnectorContract {
+    #[payable]
+    fn ft_approve(&mut self, spender_id: AccountId, amount: U128) {
+        let owner_id = env::predecessor_account_id();
+        let attached_deposit = env::attached_deposit();
+        require!(
+            attached_deposit > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let initial_storage_usage = env::storage_usage();
+        self.allowances
+            .approve(&owner_id, &spender_id, amount.0)
+            .sdk_unwrap();
+        // The attached deposit should cover the storage of the new allowance record,
+        // and the storage of the removed record is refunded to the owner who paid for it
+        let storage_usage = env::storage_usage();
+        let storage_cost = Balance::from(storage_usage.saturating_sub(initial_storage_usage))
+            * env::storage_byte_cost();
+        let storage_refund = Balance::from(initial_storage_usage.saturating_sub(storage_usage))
+            * env::storage_byte_cost();
+        if attached_deposit < storage_cost {
+            panic_err(errors::ERR_ATTACHED_DEPOSIT_NOT_ENOUGH);
+        }
+        let refund = attached_deposit - storage_cost.max(1) + storage_refund;
+        if refund > 0 {
+            Promise::new(owner_id.clone()).transfer(refund);
+        }
+        crate::log!(
+            "Allowance of @{} for @{} set to {}",
+            owner_id,
+            spender_id,
+            amount.0
+        );
+    }
+
+    fn ft_allowance(&self, owner_id: AccountId, spender_id: AccountId) -> U128 {
+        self.allowances.get(&owner_id, &spender_id).into()
+    }
+
+    #[payable]
+    fn ft_transfer_from(
+        &mut self,
+        owner_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+    ) {
+        self.ft.assert_transfer_deposit();
+        let initial_storage_usage = env::storage_usage();
+        let spender_id = env::predecessor_account_id();
+        self.allowances
+            .spend(&owner_id, &spender_id, amount.0)
+            .sdk_unwrap();
+        self.ft
+            .internal_transfer_eth_on_near(&owner_id, &receiver_id, NEP141Wei::new(amount.0), &memo)
+            .sdk_unwrap();
+        self.ft.refund_transfer_deposit(initial_storage_usage);
+    }
+
+    #[private]
+    fn set_engine_allowance_mode(&mut self, enabled: bool) {
+        self.allowances.engine_allowance_mode = enabled;
+    }
+
+    fn is_engine_allowance_mode(&self) -> bool {
+        self.allowances.engine_allowance_mode
+    }
+}
+
+#[near_bindgen]
+impl MetaTransfer for EthConnectorContract {
+    fn ft_transfer_with_signature(
+        &mut self,
+        authorization: TransferAuthorization,
+        public_key: PublicKey,
+        signature: Base64VecU8,
+    ) {
+        let relayer_id = env::predecessor_account_id();
+        // The owner submitting its own authorization pays no fee to itself
+        let fee = if relayer_id == authorization.owner_id {
+            0
         } else {
-            // Mint - calculate new balances
+            authorization.fee.0
+        };
+        if fee > 0 && !self.ft.accounts_eth.contains_key(&relayer_id) {
+            panic_err(errors::ERR_ACCOUNT_NOT_REGISTERED);
+        }
+        self.meta_transfers
+            .verify(&authorization, &public_key, &signature.0)
+            .sdk_unwrap();
+        let owner_id = authorization.owner_id;
+        self.ft
+            .internal_transfer_eth_on_near(
+                &owner_id,
+                &authorization.receiver_id,
+                NEP141Wei::new(authorization.amount.0),
+                &authorization.memo,
+            )
+            .sdk_unwrap();
+        if fee > 0 {
             self.ft
-                .mint_eth_on_near(
-                    deposit_call.new_owner_id.clone(),
-                    deposit_call.amount - NEP141Wei::new(deposit_call.fee.as_u128()),
-                )
+                .internal_transfer_eth_on_near(&owner_id, &relayer_id, NEP141Wei::new(fee), &None)
                 .sdk_unwrap();
+        }
+    }
+
+    #[payable]
+    fn ft_set_signing_key(&mut self, public_key: Option<PublicKey>) {
+        let account_id = env::predecessor_account_id();
+        let attached_deposit = env::attached_deposit();
+        require!(
+            attached_deposit > 0,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let initial_storage_usage = env::storage_usage();
+        match public_key {
+            Some(public_key) => self
+                .meta_transfers
+                .signing_keys
+                .insert(&account_id, &public_key),
+            None => self.meta_transfers.signing_keys.remove(&account_id),
+        };
+        // The attached deposit should cover the storage of the new key
+        let storage_cost =
+            Balance::from(env::storage_usage().saturating_sub(initial_storage_usage))
+                * env::storage_byte_cost();
+        if attached_deposit < storage_cost {
+            panic_err(errors::ERR_ATTACHED_DEPOSIT_NOT_ENOUGH);
+        }
+        let refund = attached_deposit - storage_cost.max(1);
+        if refund > 0 {
+            Promise::new(account_id).transfer(refund);
+        }
+    }
+
+    fn ft_signing_key_of(&self, account_id: AccountId) -> Option<PublicKey> {
+        self.meta_transfers.signing_keys.get(&account_id)
+    }
+
+    fn ft_transfer_nonce_of(&self, account_id: AccountId) -> U64 {
+        self.meta_transfers.nonce_of(&account_id).into()
+    }
+}
+
+#[near_bindgen]
+impl BalanceCheckpointManagement for EthConnectorContract {
+    fn ft_balance_at(&self, account_id: AccountId, block_height: U64) -> Option<U128> {
+        let current_balance = self.ft.ft_balance_of(account_id.clone()).0;
+        self.ft
+            .checkpoints
+            .balance_at(&account_id, current_balance, block_height.0)
+            .map(U128)
+    }
+
+    fn ft_total_supply_at(&self, block_height: U64) -> Option<U128> {
+        self.ft
+            .checkpoints
+            .total_supply_at(self.ft.ft_total_supply().0, block_height.0)
+            .map(U128)
+    }
+
+    #[private]
+    fn set_checkpoint_tracking(&mut self, enabled: bool) {
+        self.ft.checkpoints.set_track_all(enabled);
+    }
+
+    #[private]
+    fn set_account_checkpoint_tracking(&mut self, account_id: AccountId, enabled: bool) {
+        let balance = self.ft.ft_balance_of(account_id.clone()).0;
+        self.ft
+            .checkpoints
+            .set_track_account(&account_id, enabled, balance);
+    }
+
+    fn get_checkpoint_tracking_since(&self, account_id: AccountId) -> Option<U64> {
+        self.ft.checkpoints.tracking_since(&account_id).map(U64)
+    }
+
+    #[private]
+    fn prune_checkpoints(&mut self, block_height: U64, account_ids: Vec<AccountId>) {
+        self.ft.checkpoints.prune_total_supply(block_height.0);
+        for account_id in &account_ids {
             self.ft
-                .mint_eth_on_near(
-                    deposit_call.relayer_id,
-                    NEP141Wei::new(deposit_call.fee.as_u128()),
-                )
+                .checkpoints
+                .prune_account(account_id, block_height.0);
+        }
+    }
+}
+
+#[near_bindgen]
+impl RegistrationPolicyManagement for EthConnectorContract {
+    #[private]
+    fn set_registration_policy(&mut self, policy: RegistrationPolicy) {
+        self.ft.registration_policy = policy;
+    }
+
+    fn get_registration_policy(&self) -> RegistrationPolicy {
+        self.ft.registration_policy
+    }
+}
+
+#[near_bindgen]
+impl MintLimitManagement for EthConnectorContract {
+    #[private]
+    fn set_mint_limits(&mut self, limits: MintLimits) {
+        limits.validate().sdk_unwrap();
+        self.connector.mint_limiter.limits = limits;
+    }
+
+    fn get_mint_limits(&self) -> MintLimits {
+        self.connector.mint_limiter.limits.clone()
+    }
+
+    fn get_mint_window(&self) -> MintWindow {
+        self.connector
+            .mint_limiter
+            .current_window(env::block_height())
+    }
+}
+
+#[near_bindgen]
+impl PendingDepositManagement for EthConnectorContract {
+    #[private]
+    fn release_deposit(&mut self, proof_key: String) -> PromiseOrValue<Option<U128>> {
+        let deposit = self.pending_deposits.remove(&proof_key).sdk_unwrap();
+        crate::log!("Release held deposit {}", proof_key);
+        self.internal_finish_deposit(deposit.deposit_call)
+    }
+
+    #[private]
+    fn reject_deposit(&mut self, proof_key: String) {
+        self.pending_deposits.remove(&proof_key).sdk_unwrap();
+        crate::log!("Reject held deposit {}", proof_key);
+    }
+
+    fn get_pending_deposit(&self, proof_key: String) -> Option<PendingDepositView> {
+        self.pending_deposits
+            .get(&proof_key)
+            .map(|deposit| (&deposit).into())
+    }
+
+    fn get_pending_deposits(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<PendingDepositView> {
+        self.pending_deposits
+            .page(
+                from_index.map_or(0, |index| index.0),
+                limit.map_or(DEFAULT_PENDING_DEPOSITS_LIMIT, |limit| limit.0),
+            )
+            .iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn get_pending_deposits_count(&self) -> U64 {
+        self.pending_deposits.len().into()
+    }
+}
+
+#[near_bindgen]
+impl DepositHistoryView for EthConnectorContract {
+    #[private]
+    fn set_deposit_history_config(&mut self, config: DepositHistoryConfig) {
+        self.deposit_history.config = config;
+    }
+
+    fn get_deposit_history_config(&self) -> DepositHistoryConfig {
+        self.deposit_history.config.clone()
+    }
+
+    fn deposits_of(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<DepositRecordView> {
+        self.deposit_history
+            .deposits_of(
+                &account_id,
+                from_index.map_or(0, |index| index.0),
+                limit.map_or(DEFAULT_DEPOSITS_OF_LIMIT, |limit| limit.0),
+            )
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn deposits_of_address(
+        &self,
+        address: String,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<DepositRecordView> {
+        let address = Self::parse_address(&address);
+        self.deposit_history
+            .deposits_of_address(
+                &address,
+                from_index.map_or(0, |index| index.0),
+                limit.map_or(DEFAULT_DEPOSITS_OF_LIMIT, |limit| limit.0),
+            )
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn deposit_by_proof_key(&self, proof_key: String) -> Option<DepositRecordView> {
+        self.deposit_history.get(&proof_key).map(Into::into)
+    }
+}
+
+#[near_bindgen]
+impl WithdrawalLedgerView for EthConnectorContract {
+    fn get_withdrawal(&self, nonce: U64) -> Option<WithdrawalRecordView> {
+        self.withdrawals.get(nonce.0).map(Into::into)
+    }
+
+    fn withdrawals_of(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<WithdrawalRecordView> {
+        self.withdrawals
+            .withdrawals_of(
+                &account_id,
+                from_index.map_or(0, |index| index.0),
+                limit.map_or(DEFAULT_WITHDRAWALS_OF_LIMIT, |limit| limit.0),
+            )
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+}
+
+#[near_bindgen]
+impl WithdrawFeeManagement for EthConnectorContract {
+    #[private]
+    fn set_withdraw_fee_schedule(&mut self, schedule: WithdrawFeeSchedule) {
+        schedule.validate().sdk_unwrap();
+        self.connector.withdraw_fee = schedule;
+    }
+
+    fn get_withdraw_fee_schedule(&self) -> WithdrawFeeSchedule {
+        self.connector.withdraw_fee.clone()
+    }
+}
+
+#[near_bindgen]
+impl DelayedWithdraw for EthConnectorContract {
+    #[result_serializer(borsh)]
+    fn finalize_withdraw(&mut self, nonce: U64) -> WithdrawResult {
+        self.assert_withdraw_not_paused();
+        let withdrawal = self
+            .withdrawal_queue
+            .take_unlocked(nonce.0, env::block_height())
+            .sdk_unwrap();
+        self.ft
+            .internal_unlock_eth(&withdrawal.sender_id, withdrawal.amount)
+            .sdk_unwrap();
+        let result = self.internal_burn_withdrawal(
+            &withdrawal.sender_id,
+            withdrawal.recipient_address,
+            withdrawal.amount,
+            withdrawal.fee,
+            withdrawal.nonce,
+        );
+        Self::emit_withdraw_burns(&[(&withdrawal.sender_id, &result)]);
+        result
+    }
+
+    fn cancel_withdraw(&mut self, nonce: U64) {
+        let predecessor_account_id = env::predecessor_account_id();
+        let is_owner = predecessor_account_id == env::current_account_id();
+        self.withdrawal_queue
+            .assert_guardian(&predecessor_account_id, is_owner)
+            .sdk_unwrap();
+        let withdrawal = self.withdrawal_queue.remove(nonce.0).sdk_unwrap();
+        self.ft
+            .internal_unlock_eth(&withdrawal.sender_id, withdrawal.amount)
+            .sdk_unwrap();
+        if let Some(engine_id) = &withdrawal.engine_id {
+            self.connector
+                .engines
+                .record_deposit(engine_id, withdrawal.amount.as_u128())
                 .sdk_unwrap();
-            // Store proof only after `mint` calculations
-            self.ft.record_proof(&deposit_call.proof_key).sdk_unwrap();
-            PromiseOrValue::Value(None)
         }
+        crate::log!(
+            "Withdrawal {} is cancelled, refund {} to {}",
+            withdrawal.nonce,
+            withdrawal.amount,
+            withdrawal.sender_id
+        );
+    }
+
+    #[private]
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn withdraw_queued(&self, nonce: U64) {
+        crate::log!("Withdrawal {} is queued", nonce.0);
+    }
+
+    fn get_queued_withdrawal(&self, nonce: U64) -> Option<QueuedWithdrawalView> {
+        self.withdrawal_queue.get(nonce.0).map(Into::into)
+    }
+
+    #[private]
+    fn set_withdraw_delay_config(&mut self, config: WithdrawDelayConfig) {
+        self.withdrawal_queue.config = config;
+    }
+
+    fn get_withdraw_delay_config(&self) -> WithdrawDelayConfig {
+        self.withdrawal_queue.config.clone()
     }
 }
 
@@ -513,3 +1995,89 @@ impl Migration for EthConnectorContract {
         MigrationCheckResult::Success
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+    use near_sdk::{testing_env, RuntimeFeesConfig, VMConfig};
+
+    fn contract() -> EthConnectorContract {
+        let context = VMContextBuilder::new()
+            .predecessor_account_id(env::current_account_id())
+            .build();
+        testing_env!(context);
+        EthConnectorContract::new(
+            "prover".parse().unwrap(),
+            "096de9c2b8a5b8c22cee3289b101f6960d68e51e".to_string(),
+            FungibleTokenMetadata::default(),
+            "engine".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_deposit_call_fallback_when_resolve_failed() {
+        let mut contract = contract();
+        let context = VMContextBuilder::new()
+            .predecessor_account_id(env::current_account_id())
+            .build();
+        testing_env!(
+            context,
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Failed],
+        );
+        let receiver_id: AccountId = "receiver".parse().unwrap();
+
+        let result =
+            contract.finish_deposit_call_fallback(receiver_id.clone(), U128(100), String::new());
+        assert!(matches!(result, PromiseOrValue::Value(U128(100))));
+        assert!(get_created_receipts().is_empty());
+        assert_eq!(contract.ft.get_account_eth_balance(&receiver_id), None);
+    }
+
+    #[test]
+    fn test_revoked_allowance_storage_is_refunded() {
+        use near_sdk::mock::VmAction;
+
+        let mut contract = contract();
+        let owner_id: AccountId = "owner".parse().unwrap();
+        let spender_id: AccountId = "spender".parse().unwrap();
+        let approve_context = |attached_deposit: Balance| {
+            VMContextBuilder::new()
+                .predecessor_account_id(owner_id.clone())
+                .storage_usage(env::storage_usage())
+                .attached_deposit(attached_deposit)
+                .build()
+        };
+        let refunds = || -> Vec<Balance> {
+            get_created_receipts()
+                .into_iter()
+                .flat_map(|receipt| receipt.actions)
+                .filter_map(|action| match action {
+                    VmAction::Transfer { deposit } => Some(deposit),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let attached_deposit = 10u128.pow(22);
+        testing_env!(approve_context(attached_deposit));
+        let initial_storage_usage = env::storage_usage();
+        contract.ft_approve(spender_id.clone(), U128(100));
+        let storage_cost =
+            Balance::from(env::storage_usage() - initial_storage_usage) * env::storage_byte_cost();
+        assert_eq!(refunds(), vec![attached_deposit - storage_cost]);
+
+        // Lowering the allowance keeps the record
+        testing_env!(approve_context(1));
+        contract.ft_approve(spender_id.clone(), U128(50));
+        assert!(refunds().is_empty());
+
+        testing_env!(approve_context(1));
+        contract.ft_approve(spender_id.clone(), U128(0));
+        assert_eq!(refunds(), vec![storage_cost]);
+        assert_eq!(contract.ft_allowance(owner_id, spender_id).0, 0);
+    }
+}
@@ -0,0 +1,371 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    env, ext_contract,
+    json_types::{Base64VecU8, U128, U64},
+    serde::{Deserialize, Serialize},
+    AccountId, CurveType, IntoStorageKey, PublicKey,
+};
+
+/// Length of the secp256k1 signature: `r`, `s` and the recovery id `v`.
+const SECP256K1_SIGNATURE_LENGTH: usize = 65;
+const ED25519_SIGNATURE_LENGTH: usize = 64;
+const ED25519_PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Prefix of the signed message, as in the EIP-191 `personal_sign`.
+const SIGNED_MESSAGE_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+/// Domain of the signed transfers. It separates them from other messages signed with the key.
+const TRANSFER_AUTHORIZATION_DOMAIN: &str = "NEP-141 transfer authorization";
+
+/// Transfer signed off-chain by the token owner. The signed hash is the EIP-191
+/// `personal_sign` hash of the message with the domain, the connector account ID
+/// and the borsh serialized authorization. See `TransferAuthorization::message`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferAuthorization {
+    /// The connector account ID. It prevents replaying the authorization on another contract.
+    pub contract_id: AccountId,
+    pub owner_id: AccountId,
+    pub receiver_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+    /// Fee paid by the owner to the account submitting the authorization.
+    pub fee: U128,
+    /// Should be equal to the next transfer nonce of the owner.
+    pub nonce: U64,
+    /// The last block height the authorization can be submitted at.
+    pub valid_until_block: U64,
+}
+
+impl TransferAuthorization {
+    /// Message to be signed: the borsh serialized domain, connector account ID
+    /// and the authorization.
+    pub fn message(&self, contract_id: &AccountId) -> Vec<u8> {
+        (TRANSFER_AUTHORIZATION_DOMAIN, contract_id, self)
+            .try_to_vec()
+            .unwrap()
+    }
+
+    /// Keccak256 hash of the message with the `"\x19Ethereum Signed Message:\n" + len` prefix.
+    pub fn hash(&self, contract_id: &AccountId) -> [u8; 32] {
+        let message = self.message(contract_id);
+        let mut data = format!("{}{}", SIGNED_MESSAGE_PREFIX, message.len()).into_bytes();
+        data.extend_from_slice(&message);
+        env::keccak256_array(&data)
+    }
+}
+
+/// Signing keys and nonces of the signed transfers.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct MetaTransfers {
+    pub nonces: LookupMap<AccountId, u64>,
+    pub signing_keys: LookupMap<AccountId, PublicKey>,
+}
+
+impl MetaTransfers {
+    pub fn new<S: IntoStorageKey>(prefix_nonces: S, prefix_signing_keys: S) -> Self {
+        Self {
+            nonces: LookupMap::new(prefix_nonces),
+            signing_keys: LookupMap::new(prefix_signing_keys),
+        }
+    }
+
+    pub fn nonce_of(&self, account_id: &AccountId) -> u64 {
+        self.nonces.get(account_id).unwrap_or(0)
+    }
+
+    /// Check the authorization and its signature, and consume the nonce of the owner.
+    pub fn verify(
+        &mut self,
+        authorization: &TransferAuthorization,
+        public_key: &PublicKey,
+        signature: &[u8],
+    ) -> Result<(), error::MetaTransferError> {
+        if authorization.contract_id != env::current_account_id() {
+            return Err(error::MetaTransferError::InvalidContractId);
+        }
+        if env::block_height() > authorization.valid_until_block.0 {
+            return Err(error::MetaTransferError::Expired);
+        }
+        let nonce = self.nonce_of(&authorization.owner_id);
+        if authorization.nonce.0 != nonce {
+            return Err(error::MetaTransferError::InvalidNonce);
+        }
+        if !self.is_signing_key(&authorization.owner_id, public_key) {
+            return Err(error::MetaTransferError::UnknownSigningKey);
+        }
+        let hash = authorization.hash(&env::current_account_id());
+        verify_signature(&hash, public_key, signature)?;
+        self.nonces.insert(&authorization.owner_id, &(nonce + 1));
+        Ok(())
+    }
+
+    /// The registered key of the account, or the key of the ed25519 implicit account.
+    fn is_signing_key(&self, account_id: &AccountId, public_key: &PublicKey) -> bool {
+        match self.signing_keys.get(account_id) {
+            Some(signing_key) => signing_key == *public_key,
+            None => {
+                matches!(public_key.curve_type(), CurveType::ED25519)
+                    && account_id.as_str() == hex::encode(&public_key.as_bytes()[1..])
+            }
+        }
+    }
+}
+
+fn verify_signature(
+    hash: &[u8; 32],
+    public_key: &PublicKey,
+    signature: &[u8],
+) -> Result<(), error::MetaTransferError> {
+    let key_data = &public_key.as_bytes()[1..];
+    let is_valid = match public_key.curve_type() {
+        CurveType::ED25519 => {
+            if signature.len() != ED25519_SIGNATURE_LENGTH
+                || key_data.len() != ED25519_PUBLIC_KEY_LENGTH
+            {
+                return Err(error::MetaTransferError::Parse);
+            }
+            ed25519_verify(signature, hash, key_data)
+        }
+        CurveType::SECP256K1 => {
+            if signature.len() != SECP256K1_SIGNATURE_LENGTH {
+                return Err(error::MetaTransferError::Parse);
+            }
+            let v = signature[64];
+            // Accept both the raw recovery id and the Ethereum style one
+            let v = if v >= 27 { v - 27 } else { v };
+            // Reject the malleable signatures
+            matches!(
+                env::ecrecover(hash, &signature[..64], v, true),
+                Some(recovered) if recovered[..] == *key_data
+            )
+        }
+    };
+    if is_valid {
+        Ok(())
+    } else {
+        Err(error::MetaTransferError::InvalidSignature)
+    }
+}
+
+/// The `ed25519_verify` host function, which near-sdk doesn't expose.
+#[cfg(target_arch = "wasm32")]
+mod host {
+    extern "C" {
+        pub fn ed25519_verify(
+            sig_len: u64,
+            sig_ptr: u64,
+            msg_len: u64,
+            msg_ptr: u64,
+            pub_key_len: u64,
+            pub_key_ptr: u64,
+        ) -> u64;
+    }
+}
+
+/// Verify the ed25519 signature with the `ed25519_verify` host function.
+#[cfg(target_arch = "wasm32")]
+fn ed25519_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> bool {
+    // SAFETY: the host function reads the memory of the passed slices only.
+    // The lengths of the signature and the key are checked by the caller.
+    unsafe {
+        host::ed25519_verify(
+            signature.len() as _,
+            signature.as_ptr() as _,
+            message.len() as _,
+            message.as_ptr() as _,
+            public_key.len() as _,
+            public_key.as_ptr() as _,
+        ) == 1
+    }
+}
+
+/// The mocked blockchain has no `ed25519_verify` host function.
+#[cfg(not(target_arch = "wasm32"))]
+fn ed25519_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> bool {
+    use ed25519_dalek::Verifier;
+    match (
+        ed25519_dalek::PublicKey::from_bytes(public_key),
+        ed25519_dalek::Signature::try_from(signature),
+    ) {
+        (Ok(public_key), Ok(signature)) => public_key.verify(message, &signature).is_ok(),
+        _ => false,
+    }
+}
+
+#[ext_contract(ext_meta_transfer)]
+pub trait MetaTransfer {
+    /// Transfer tokens with the authorization signed by the owner. The predecessor,
+    /// which pays for the gas, receives the fee of the authorization. The predecessor
+    /// should be registered, the fee is not charged if it is the owner.
+    ///
+    /// The signature is checked against the key registered with `ft_set_signing_key`,
+    /// or against the account ID itself for ed25519 implicit accounts.
+    /// The secp256k1 signature is 65 bytes long, including the recovery id.
+    fn ft_transfer_with_signature(
+        &mut self,
+        authorization: TransferAuthorization,
+        public_key: PublicKey,
+        signature: Base64VecU8,
+    );
+
+    /// Set the key signing the transfers of the predecessor. `None` removes the key.
+    /// Requires at least 1 yoctoNEAR attached, and the storage cost of the new key.
+    fn ft_set_signing_key(&mut self, public_key: Option<PublicKey>);
+
+    fn ft_signing_key_of(&self, account_id: AccountId) -> Option<PublicKey>;
+
+    /// Return the nonce expected in the next authorization of the account.
+    fn ft_transfer_nonce_of(&self, account_id: AccountId) -> U64;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum MetaTransferError {
+        Parse,
+        InvalidSignature,
+        InvalidNonce,
+        InvalidContractId,
+        Expired,
+        UnknownSigningKey,
+    }
+
+    impl AsRef<[u8]> for MetaTransferError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::Parse => errors::ERR_META_TX_PARSE.as_bytes(),
+                Self::InvalidSignature => errors::ERR_INVALID_SIGNATURE,
+                Self::InvalidNonce => errors::ERR_INVALID_NONCE,
+                Self::InvalidContractId => errors::ERR_INVALID_CONTRACT_ID,
+                Self::Expired => errors::ERR_AUTHORIZATION_EXPIRED,
+                Self::UnknownSigningKey => errors::ERR_UNKNOWN_SIGNING_KEY,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    fn keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn public_key(keypair: &ed25519_dalek::Keypair) -> PublicKey {
+        let mut data = vec![CurveType::ED25519 as u8];
+        data.extend_from_slice(&keypair.public.to_bytes());
+        PublicKey::try_from(data).unwrap()
+    }
+
+    fn authorization(owner_id: AccountId, nonce: u64) -> TransferAuthorization {
+        TransferAuthorization {
+            contract_id: "eth_connector.root".parse().unwrap(),
+            owner_id,
+            receiver_id: "bob".parse().unwrap(),
+            amount: 100.into(),
+            memo: None,
+            fee: 1.into(),
+            nonce: nonce.into(),
+            valid_until_block: 10.into(),
+        }
+    }
+
+    fn contract_id() -> AccountId {
+        "eth_connector.root".parse().unwrap()
+    }
+
+    fn set_env(block_height: u64) {
+        testing_env!(VMContextBuilder::new()
+            .current_account_id("eth_connector.root".parse().unwrap())
+            .block_index(block_height)
+            .build());
+    }
+
+    #[test]
+    fn test_implicit_account_signature() {
+        set_env(1);
+        let keypair = keypair();
+        let owner_id: AccountId = hex::encode(keypair.public.to_bytes()).parse().unwrap();
+        let mut meta_transfers = MetaTransfers::new(b"n".to_vec(), b"k".to_vec());
+
+        let auth = authorization(owner_id.clone(), 0);
+        let signature = keypair.sign(&auth.hash(&contract_id())).to_bytes();
+        meta_transfers
+            .verify(&auth, &public_key(&keypair), &signature)
+            .unwrap();
+        assert_eq!(meta_transfers.nonce_of(&owner_id), 1);
+        // The nonce is consumed
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::InvalidNonce)
+        ));
+
+        let mut auth = authorization(owner_id, 1);
+        let signature = keypair.sign(&auth.hash(&contract_id())).to_bytes();
+        auth.amount = 1000.into();
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_signing_key() {
+        set_env(1);
+        let keypair = keypair();
+        let owner_id: AccountId = "alice".parse().unwrap();
+        let mut meta_transfers = MetaTransfers::new(b"n".to_vec(), b"k".to_vec());
+
+        let auth = authorization(owner_id.clone(), 0);
+        let signature = keypair.sign(&auth.hash(&contract_id())).to_bytes();
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::UnknownSigningKey)
+        ));
+
+        meta_transfers
+            .signing_keys
+            .insert(&owner_id, &public_key(&keypair));
+        set_env(11);
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::Expired)
+        ));
+        set_env(10);
+        meta_transfers
+            .verify(&auth, &public_key(&keypair), &signature)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_signature_bound_to_contract() {
+        set_env(1);
+        let keypair = keypair();
+        let owner_id: AccountId = hex::encode(keypair.public.to_bytes()).parse().unwrap();
+        let mut meta_transfers = MetaTransfers::new(b"n".to_vec(), b"k".to_vec());
+
+        let auth = authorization(owner_id, 0);
+        let other_contract_id: AccountId = "other_connector.root".parse().unwrap();
+        assert_ne!(auth.hash(&contract_id()), auth.hash(&other_contract_id));
+        // The signature for another connector is rejected
+        let signature = keypair.sign(&auth.hash(&other_contract_id)).to_bytes();
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::InvalidSignature)
+        ));
+        // The raw authorization is not the signed message
+        let signature = keypair.sign(&auth.try_to_vec().unwrap()).to_bytes();
+        assert!(matches!(
+            meta_transfers.verify(&auth, &public_key(&keypair), &signature),
+            Err(error::MetaTransferError::InvalidSignature)
+        ));
+    }
+}
@@ -1,8 +1,42 @@
-use aurora_engine_types::types::NEP141Wei;
+use crate::admin_controlled::PausedMask;
+use crate::fungible_token::metadata::FungibleTokenMetadata;
+use aurora_engine_types::types::{Address, NEP141Wei};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LazyOption, LookupMap};
 use near_sdk::{ext_contract, AccountId, StorageUsage};
 use std::collections::HashMap;
 
+/// Version of the contract state layout. The state without the stored version
+/// has the layout of `LegacyEthConnectorContract`.
+pub const STATE_VERSION: u32 = 1;
+
+/// Contract state stored before the state versioning.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LegacyEthConnectorContract {
+    pub connector: LegacyEthConnector,
+    pub ft: LegacyFungibleToken,
+    pub metadata: LazyOption<FungibleTokenMetadata>,
+}
+
+/// Connector data stored before the state versioning.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LegacyEthConnector {
+    pub prover_account: AccountId,
+    pub eth_custodian_address: Address,
+    pub paused_mask: PausedMask,
+    pub account_with_access_right: AccountId,
+}
+
+/// Fungible token data stored before the state versioning.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LegacyFungibleToken {
+    pub accounts_eth: LookupMap<AccountId, NEP141Wei>,
+    pub total_eth_supply_on_near: NEP141Wei,
+    pub account_storage_usage: StorageUsage,
+    pub statistics_aurora_accounts_counter: u64,
+    pub used_proofs: LookupMap<String, bool>,
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct MigrationInputData {
     pub accounts_eth: HashMap<AccountId, NEP141Wei>,
@@ -33,3 +67,64 @@ pub trait Migration {
         #[serializer(borsh)] data: MigrationInputData,
     ) -> MigrationCheckResult;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EthConnectorContract, StorageKey};
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::{env, testing_env};
+
+    fn write_legacy_state(account_id: &AccountId) {
+        let mut accounts_eth = LookupMap::new(StorageKey::FungibleTokenEth);
+        accounts_eth.insert(account_id, &NEP141Wei::new(100));
+        let mut used_proofs = LookupMap::new(StorageKey::Proof);
+        used_proofs.insert(&"proof".to_string(), &true);
+        env::state_write(&LegacyEthConnectorContract {
+            connector: LegacyEthConnector {
+                prover_account: "prover".parse().unwrap(),
+                eth_custodian_address: Address::decode("096de9c2b8a5b8c22cee3289b101f6960d68e51e")
+                    .unwrap(),
+                paused_mask: 1,
+                account_with_access_right: "engine".parse().unwrap(),
+            },
+            ft: LegacyFungibleToken {
+                accounts_eth,
+                total_eth_supply_on_near: NEP141Wei::new(100),
+                account_storage_usage: 10,
+                statistics_aurora_accounts_counter: 1,
+                used_proofs,
+            },
+            metadata: LazyOption::new(
+                StorageKey::Metadata,
+                Some(&FungibleTokenMetadata::default()),
+            ),
+        });
+    }
+
+    #[test]
+    fn test_migrate_state() {
+        testing_env!(VMContextBuilder::new().build());
+        let alice: AccountId = "alice".parse().unwrap();
+        write_legacy_state(&alice);
+
+        let contract = EthConnectorContract::migrate_state();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(contract.connector.prover_account.as_str(), "prover");
+        assert_eq!(contract.connector.paused_mask, 1);
+        assert_eq!(
+            contract.ft.accounts_eth.get(&alice),
+            Some(NEP141Wei::new(100))
+        );
+        assert_eq!(contract.ft.total_eth_supply_on_near, NEP141Wei::new(100));
+        assert_eq!(contract.ft.account_storage_usage, 10);
+        assert_eq!(contract.ft.statistics_aurora_accounts_counter, 1);
+        assert!(contract.ft.is_used_event("proof"));
+        assert_eq!(
+            contract.metadata.get().unwrap().symbol,
+            FungibleTokenMetadata::default().symbol
+        );
+        // The legacy accounts have no recorded storage deposit
+        assert_eq!(contract.ft.storage_deposits.deposit_of(&alice), 0);
+    }
+}
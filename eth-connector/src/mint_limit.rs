@@ -0,0 +1,198 @@
+use crate::pending_deposit::HoldReason;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    Balance, BlockHeight,
+};
+
+/// Limits of the minted amount. The default limits are off.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintLimits {
+    /// Length of the window in blocks.
+    pub window_blocks: U64,
+    /// Maximum total amount minted in the window.
+    pub max_window_mint: Option<U128>,
+    /// Maximum amount minted by a single deposit.
+    pub max_single_deposit: Option<U128>,
+}
+
+impl Default for MintLimits {
+    fn default() -> Self {
+        Self {
+            window_blocks: 0.into(),
+            max_window_mint: None,
+            max_single_deposit: None,
+        }
+    }
+}
+
+impl MintLimits {
+    pub fn validate(&self) -> Result<(), error::MintLimitError> {
+        if self.max_window_mint.is_some() && self.window_blocks.0 == 0 {
+            return Err(error::MintLimitError::InvalidWindow);
+        }
+        Ok(())
+    }
+}
+
+/// Current mint window.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintWindow {
+    /// Block height the window started at.
+    pub start_block: U64,
+    /// Amount minted in the window.
+    pub minted: U128,
+}
+
+impl Default for MintWindow {
+    fn default() -> Self {
+        Self {
+            start_block: 0.into(),
+            minted: 0.into(),
+        }
+    }
+}
+
+/// Checks deposits against the mint limits and tracks the minted amount.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct MintLimiter {
+    pub limits: MintLimits,
+    pub window: MintWindow,
+}
+
+impl MintLimiter {
+    /// Check the amount against the limits and add it to the current window.
+    /// Returns the reason to hold the deposit if a limit is exceeded.
+    pub fn try_mint(
+        &mut self,
+        amount: Balance,
+        block_height: BlockHeight,
+    ) -> Result<(), HoldReason> {
+        if let Some(max_single_deposit) = self.limits.max_single_deposit {
+            if amount > max_single_deposit.0 {
+                return Err(HoldReason::SingleDepositLimit);
+            }
+        }
+
+        let mut window = self.current_window(block_height);
+        let minted = window.minted.0.saturating_add(amount);
+        if let Some(max_window_mint) = self.limits.max_window_mint {
+            if minted > max_window_mint.0 {
+                return Err(HoldReason::MintWindowLimit);
+            }
+        }
+        window.minted = minted.into();
+        self.window = window;
+        Ok(())
+    }
+
+    /// Window for the block height. A new window starts when the previous one is over.
+    pub fn current_window(&self, block_height: BlockHeight) -> MintWindow {
+        let window_end = self
+            .window
+            .start_block
+            .0
+            .saturating_add(self.limits.window_blocks.0);
+        if block_height >= window_end {
+            MintWindow {
+                start_block: block_height.into(),
+                minted: 0.into(),
+            }
+        } else {
+            self.window.clone()
+        }
+    }
+}
+
+#[ext_contract(ext_mint_limit)]
+pub trait MintLimitManagement {
+    /// Set the limits of the minted amount.
+    fn set_mint_limits(&mut self, limits: MintLimits);
+
+    fn get_mint_limits(&self) -> MintLimits;
+
+    /// Return the mint window for the current block.
+    fn get_mint_window(&self) -> MintWindow;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum MintLimitError {
+        InvalidWindow,
+    }
+
+    impl AsRef<[u8]> for MintLimitError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::InvalidWindow => errors::ERR_INVALID_MINT_WINDOW,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> MintLimiter {
+        MintLimiter {
+            limits: MintLimits {
+                window_blocks: 10.into(),
+                max_window_mint: Some(100.into()),
+                max_single_deposit: Some(60.into()),
+            },
+            window: MintWindow::default(),
+        }
+    }
+
+    #[test]
+    fn test_no_limits() {
+        let mut limiter = MintLimiter::default();
+        assert!(limiter.try_mint(u128::MAX, 1).is_ok());
+        assert!(limiter.try_mint(u128::MAX, 1).is_ok());
+    }
+
+    #[test]
+    fn test_single_deposit_limit() {
+        let mut limiter = limiter();
+        assert!(matches!(
+            limiter.try_mint(61, 1),
+            Err(HoldReason::SingleDepositLimit)
+        ));
+        assert!(limiter.try_mint(60, 1).is_ok());
+    }
+
+    #[test]
+    fn test_window_limit() {
+        let mut limiter = limiter();
+        assert!(limiter.try_mint(60, 100).is_ok());
+        assert!(limiter.try_mint(40, 105).is_ok());
+        assert!(matches!(
+            limiter.try_mint(1, 109),
+            Err(HoldReason::MintWindowLimit)
+        ));
+        assert_eq!(limiter.window.minted, 100.into());
+        // The next window
+        assert!(limiter.try_mint(50, 110).is_ok());
+        assert_eq!(limiter.window.start_block, 110.into());
+        assert_eq!(limiter.window.minted, 50.into());
+    }
+
+    #[test]
+    fn test_validate() {
+        let limits = MintLimits {
+            max_window_mint: Some(100.into()),
+            ..MintLimits::default()
+        };
+        assert!(matches!(
+            limits.validate(),
+            Err(error::MintLimitError::InvalidWindow)
+        ));
+    }
+}
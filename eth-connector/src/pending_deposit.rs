@@ -0,0 +1,145 @@
+use crate::connector_impl::{FinishDepositCallArgs, FinishDepositView};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    BlockHeight, IntoStorageKey, PromiseOrValue,
+};
+
+/// Default page size of the pending deposits view.
+pub const DEFAULT_PENDING_DEPOSITS_LIMIT: u64 = 50;
+
+/// Reason why the deposit was held instead of minted.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum HoldReason {
+    /// Total minted amount in the window would exceed the limit.
+    MintWindowLimit,
+    /// Deposit amount exceeds the single deposit limit.
+    SingleDepositLimit,
+    /// Recipient or receiver of the transfer call is in the denylist.
+    RecipientDenied,
+    /// Recipient, relayer or treasury of the deposit has no storage registration.
+    RecipientNotRegistered,
+    /// Ethereum sender of the deposit is marked as suspicious.
+    Suspicious,
+}
+
+/// Verified deposit which wasn't minted.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingDeposit {
+    pub deposit_call: FinishDepositCallArgs,
+    pub reason: HoldReason,
+    pub block_height: BlockHeight,
+}
+
+/// JSON view of `PendingDeposit`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingDepositView {
+    pub deposit_call: FinishDepositView,
+    pub reason: HoldReason,
+    pub block_height: U64,
+}
+
+impl From<&PendingDeposit> for PendingDepositView {
+    fn from(deposit: &PendingDeposit) -> Self {
+        Self {
+            deposit_call: (&deposit.deposit_call).into(),
+            reason: deposit.reason,
+            block_height: deposit.block_height.into(),
+        }
+    }
+}
+
+/// Store of held deposits by proof key.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PendingDeposits {
+    pub deposits: UnorderedMap<String, PendingDeposit>,
+}
+
+impl PendingDeposits {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            deposits: UnorderedMap::new(prefix),
+        }
+    }
+
+    pub fn insert(&mut self, deposit: &PendingDeposit) {
+        self.deposits
+            .insert(&deposit.deposit_call.proof_key, deposit);
+    }
+
+    pub fn get(&self, proof_key: &str) -> Option<PendingDeposit> {
+        self.deposits.get(&proof_key.to_string())
+    }
+
+    pub fn remove(
+        &mut self,
+        proof_key: &str,
+    ) -> Result<PendingDeposit, error::PendingDepositError> {
+        self.deposits
+            .remove(&proof_key.to_string())
+            .ok_or(error::PendingDepositError::NotFound)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.deposits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deposits.is_empty()
+    }
+
+    /// Return a page of held deposits.
+    pub fn page(&self, from_index: u64, limit: u64) -> Vec<PendingDeposit> {
+        self.deposits
+            .values_as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+#[ext_contract(ext_pending_deposit)]
+pub trait PendingDepositManagement {
+    /// Mint the held deposit bypassing the mint limits.
+    fn release_deposit(&mut self, proof_key: String) -> PromiseOrValue<Option<U128>>;
+
+    /// Drop the held deposit. The proof stays used.
+    fn reject_deposit(&mut self, proof_key: String);
+
+    fn get_pending_deposit(&self, proof_key: String) -> Option<PendingDepositView>;
+
+    /// Return held deposits starting from `from_index`, at most `limit` items.
+    fn get_pending_deposits(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<PendingDepositView>;
+
+    fn get_pending_deposits_count(&self) -> U64;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum PendingDepositError {
+        NotFound,
+    }
+
+    impl AsRef<[u8]> for PendingDepositError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::NotFound => errors::ERR_PENDING_DEPOSIT_NOT_FOUND,
+            }
+        }
+    }
+}
@@ -1,14 +1,18 @@
 use crate::{errors::ERR_BORSH_SERIALIZE, types::SdkUnwrap};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::serde::Deserialize;
+use near_sdk::serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, BorshDeserialize, BorshSerialize, Deserialize, Clone)]
+#[derive(Debug, Default, BorshDeserialize, BorshSerialize, Deserialize, Serialize, Clone)]
 pub struct Proof {
     pub log_index: u64,
+    #[serde(with = "bytes")]
     pub log_entry_data: Vec<u8>,
     pub receipt_index: u64,
+    #[serde(with = "bytes")]
     pub receipt_data: Vec<u8>,
+    #[serde(with = "bytes")]
     pub header_data: Vec<u8>,
+    #[serde(with = "bytes_list")]
     pub proof: Vec<Vec<u8>>,
 }
 
@@ -32,3 +36,109 @@ impl Proof {
             .collect()
     }
 }
+
+/// JSON representation of a byte field. Strings with the `0x` prefix are decoded as hex,
+/// other strings as base64. Arrays of numbers are the legacy representation.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonBytes {
+    Encoded(String),
+    Legacy(Vec<u8>),
+}
+
+impl JsonBytes {
+    fn decode(self) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Encoded(value) => match value.strip_prefix("0x") {
+                Some(hex_value) => hex::decode(hex_value).map_err(|e| e.to_string()),
+                None => near_sdk::base64::decode(&value).map_err(|e| e.to_string()),
+            },
+            Self::Legacy(value) => Ok(value),
+        }
+    }
+}
+
+fn encode_hex(value: &[u8]) -> String {
+    format!("0x{}", hex::encode(value))
+}
+
+/// Serde adapter for `Vec<u8>` fields: serialized as `0x`-hex string.
+pub mod bytes {
+    use super::{encode_hex, JsonBytes};
+    use near_sdk::serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode_hex(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        JsonBytes::deserialize(deserializer)?
+            .decode()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Serde adapter for `Vec<Vec<u8>>` fields: serialized as an array of `0x`-hex strings.
+pub mod bytes_list {
+    use super::{encode_hex, JsonBytes};
+    use near_sdk::serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(value.len()))?;
+        for item in value {
+            seq.serialize_element(&encode_hex(item))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        Vec::<JsonBytes>::deserialize(deserializer)?
+            .into_iter()
+            .map(|item| item.decode().map_err(de::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Proof;
+
+    fn proof() -> Proof {
+        Proof {
+            log_index: 1,
+            log_entry_data: vec![0xde, 0xad],
+            receipt_index: 2,
+            receipt_data: vec![],
+            header_data: vec![0xbe, 0xef],
+            proof: vec![vec![1, 2], vec![3]],
+        }
+    }
+
+    #[test]
+    fn test_proof_serialize_hex() {
+        assert_eq!(
+            serde_json::to_string(&proof()).unwrap(),
+            r#"{"log_index":1,"log_entry_data":"0xdead","receipt_index":2,"receipt_data":"0x","header_data":"0xbeef","proof":["0x0102","0x03"]}"#
+        );
+    }
+
+    #[test]
+    fn test_proof_deserialize_all_forms() {
+        let hex = r#"{"log_index":1,"log_entry_data":"0xdead","receipt_index":2,"receipt_data":"0x","header_data":"0xbeef","proof":["0x0102","0x03"]}"#;
+        let base64 = r#"{"log_index":1,"log_entry_data":"3q0=","receipt_index":2,"receipt_data":"","header_data":"vu8=","proof":["AQI=","Aw=="]}"#;
+        let legacy = r#"{"log_index":1,"log_entry_data":[222,173],"receipt_index":2,"receipt_data":[],"header_data":[190,239],"proof":[[1,2],[3]]}"#;
+        let expected = serde_json::to_string(&proof()).unwrap();
+        for json in [hex, base64, legacy] {
+            let parsed: Proof = serde_json::from_str(json).unwrap();
+            assert_eq!(serde_json::to_string(&parsed).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_proof_deserialize_invalid_hex() {
+        let json = r#"{"log_index":1,"log_entry_data":"0xzz","receipt_index":2,"receipt_data":"0x","header_data":"0x","proof":[]}"#;
+        assert!(serde_json::from_str::<Proof>(json).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use aurora_engine_types::types::Fee;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::{Deserialize, Serialize},
+    AccountId, Balance, BlockHeight, IntoStorageKey,
+};
+
+/// Default number of blocks the stake stays locked after the relayer unregisters.
+pub const DEFAULT_RELAYER_UNBONDING_BLOCKS: u64 = 86_400;
+
+/// Registered relayer data.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RelayerInfo {
+    /// NEAR bond attached on registration. It's returned after the unbonding delay.
+    pub stake: U128,
+    /// Number of finished deposits submitted by the relayer.
+    pub deposits_submitted: U64,
+    /// Sum of the deposit fees minted to the relayer.
+    pub fees_earned: U128,
+    /// The block height the stake can be withdrawn at, if the relayer has unregistered.
+    pub unbonding_until: Option<U64>,
+}
+
+/// Registry of relayers allowed to submit deposits.
+/// While `allowlist_mode` is off, any account with access right can submit deposits.
+/// The unregistered relayers can't submit deposits, and their stake stays locked
+/// for `unbonding_blocks`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RelayerRegistry {
+    pub relayers: LookupMap<AccountId, RelayerInfo>,
+    pub allowlist_mode: bool,
+    pub min_stake: Balance,
+    pub unbonding_blocks: u64,
+}
+
+impl RelayerRegistry {
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            relayers: LookupMap::new(prefix),
+            allowlist_mode: false,
+            min_stake: 0,
+            unbonding_blocks: DEFAULT_RELAYER_UNBONDING_BLOCKS,
+        }
+    }
+
+    pub fn get(&self, account_id: &AccountId) -> Option<RelayerInfo> {
+        self.relayers.get(account_id)
+    }
+
+    /// Register the relayer or top up the stake of the registered one.
+    /// The unbonding of the relayer is cancelled.
+    pub fn register(
+        &mut self,
+        account_id: &AccountId,
+        stake: Balance,
+    ) -> Result<RelayerInfo, error::RelayerError> {
+        let mut relayer = self.get(account_id).unwrap_or(RelayerInfo {
+            stake: 0.into(),
+            deposits_submitted: 0.into(),
+            fees_earned: 0.into(),
+            unbonding_until: None,
+        });
+        let total_stake = relayer
+            .stake
+            .0
+            .checked_add(stake)
+            .ok_or(error::RelayerError::StakeOverflow)?;
+        if total_stake < self.min_stake {
+            return Err(error::RelayerError::StakeTooLow);
+        }
+        relayer.stake = total_stake.into();
+        relayer.unbonding_until = None;
+        self.relayers.insert(account_id, &relayer);
+        Ok(relayer)
+    }
+
+    /// Start the unbonding of the relayer and return the block height the stake
+    /// can be withdrawn at.
+    pub fn unregister(
+        &mut self,
+        account_id: &AccountId,
+        block_height: BlockHeight,
+    ) -> Result<BlockHeight, error::RelayerError> {
+        let mut relayer = self
+            .get(account_id)
+            .ok_or(error::RelayerError::NotRegistered)?;
+        if relayer.unbonding_until.is_some() {
+            return Err(error::RelayerError::Unbonding);
+        }
+        let unbonding_until = block_height
+            .checked_add(self.unbonding_blocks)
+            .ok_or(error::RelayerError::StakeOverflow)?;
+        relayer.unbonding_until = Some(unbonding_until.into());
+        self.relayers.insert(account_id, &relayer);
+        Ok(unbonding_until)
+    }
+
+    /// Remove the unbonded relayer and return its data.
+    pub fn withdraw_stake(
+        &mut self,
+        account_id: &AccountId,
+        block_height: BlockHeight,
+    ) -> Result<RelayerInfo, error::RelayerError> {
+        let relayer = self
+            .get(account_id)
+            .ok_or(error::RelayerError::NotRegistered)?;
+        match relayer.unbonding_until {
+            None => return Err(error::RelayerError::NotUnbonding),
+            Some(unbonding_until) if block_height < unbonding_until.0 => {
+                return Err(error::RelayerError::StakeLocked)
+            }
+            Some(_) => {}
+        }
+        self.relayers.remove(account_id);
+        Ok(relayer)
+    }
+
+    /// Check if the account is allowed to submit deposits.
+    pub fn assert_relayer(&self, account_id: &AccountId) -> Result<(), error::RelayerError> {
+        match self.get(account_id) {
+            Some(relayer) if relayer.unbonding_until.is_some() => {
+                Err(error::RelayerError::Unbonding)
+            }
+            None if self.allowlist_mode => Err(error::RelayerError::NotRegistered),
+            _ => Ok(()),
+        }
+    }
+
+    /// Update counters of the relayer for the finished deposit. Unregistered relayers are skipped.
+    pub fn record_deposit(&mut self, account_id: &AccountId, fee: Fee) {
+        if let Some(mut relayer) = self.get(account_id) {
+            relayer.deposits_submitted = (relayer.deposits_submitted.0 + 1).into();
+            relayer.fees_earned = relayer.fees_earned.0.saturating_add(fee.as_u128()).into();
+            self.relayers.insert(account_id, &relayer);
+        }
+    }
+}
+
+#[ext_contract(ext_relayer_registry)]
+pub trait RelayerManagement {
+    /// Register the predecessor as a relayer. The attached deposit is the stake bond.
+    /// Calling it again tops up the stake.
+    fn register_relayer(&mut self) -> RelayerInfo;
+
+    /// Start the unbonding of the predecessor and return the block height the stake
+    /// can be withdrawn at. The relayer can't submit deposits while unbonding.
+    /// Requires exactly 1 yoctoNEAR attached.
+    fn unregister_relayer(&mut self) -> U64;
+
+    /// Return the stake bond of the unbonded predecessor and remove the relayer.
+    /// Requires exactly 1 yoctoNEAR attached.
+    fn withdraw_relayer_stake(&mut self) -> U128;
+
+    fn get_relayer(&self, account_id: AccountId) -> Option<RelayerInfo>;
+
+    /// Turn on refusing deposits from unregistered relayers.
+    fn set_relayer_allowlist_mode(&mut self, enabled: bool);
+
+    fn is_relayer_allowlist_mode(&self) -> bool;
+
+    /// Set the minimum stake bond for registration.
+    fn set_relayer_min_stake(&mut self, min_stake: U128);
+
+    fn get_relayer_min_stake(&self) -> U128;
+
+    /// Set the number of blocks the stake stays locked after unregistration.
+    fn set_relayer_unbonding_blocks(&mut self, unbonding_blocks: U64);
+
+    fn get_relayer_unbonding_blocks(&self) -> U64;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum RelayerError {
+        NotRegistered,
+        StakeTooLow,
+        StakeOverflow,
+        Unbonding,
+        NotUnbonding,
+        StakeLocked,
+    }
+
+    impl AsRef<[u8]> for RelayerError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::NotRegistered => errors::ERR_RELAYER_NOT_REGISTERED,
+                Self::StakeTooLow => errors::ERR_RELAYER_STAKE_TOO_LOW,
+                Self::StakeOverflow => errors::ERR_OVERFLOW_NUMBER,
+                Self::Unbonding => errors::ERR_RELAYER_UNBONDING,
+                Self::NotUnbonding => errors::ERR_RELAYER_NOT_UNBONDING,
+                Self::StakeLocked => errors::ERR_RELAYER_STAKE_LOCKED,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_unbonding() {
+        testing_env!(VMContextBuilder::new().build());
+        let relayer_id: AccountId = "relayer".parse().unwrap();
+        let mut registry = RelayerRegistry::new(b"r".to_vec());
+        registry.allowlist_mode = true;
+        registry.unbonding_blocks = 10;
+        registry.register(&relayer_id, 100).unwrap();
+        assert!(matches!(
+            registry.withdraw_stake(&relayer_id, 1),
+            Err(error::RelayerError::NotUnbonding)
+        ));
+
+        assert_eq!(registry.unregister(&relayer_id, 5).unwrap(), 15);
+        assert!(matches!(
+            registry.assert_relayer(&relayer_id),
+            Err(error::RelayerError::Unbonding)
+        ));
+        assert!(matches!(
+            registry.unregister(&relayer_id, 6),
+            Err(error::RelayerError::Unbonding)
+        ));
+        assert!(matches!(
+            registry.withdraw_stake(&relayer_id, 14),
+            Err(error::RelayerError::StakeLocked)
+        ));
+        assert_eq!(
+            registry.withdraw_stake(&relayer_id, 15).unwrap().stake.0,
+            100
+        );
+        assert!(registry.get(&relayer_id).is_none());
+    }
+
+    #[test]
+    fn test_register_cancels_unbonding() {
+        testing_env!(VMContextBuilder::new().build());
+        let relayer_id: AccountId = "relayer".parse().unwrap();
+        let mut registry = RelayerRegistry::new(b"r".to_vec());
+        registry.register(&relayer_id, 100).unwrap();
+        registry.unregister(&relayer_id, 5).unwrap();
+        let relayer = registry.register(&relayer_id, 10).unwrap();
+        assert_eq!(relayer.stake.0, 110);
+        assert_eq!(relayer.unbonding_until, None);
+        registry.assert_relayer(&relayer_id).unwrap();
+    }
+}
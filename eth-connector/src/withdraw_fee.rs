@@ -0,0 +1,138 @@
+use crate::fee_policy::{basis_points, MAX_BASIS_POINTS};
+use aurora_engine_types::types::NEP141Wei;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    ext_contract,
+    json_types::U128,
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Withdraw fee schedule: the flat fee plus basis points of the withdrawn amount.
+/// The default schedule has no fee.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawFeeSchedule {
+    pub flat_fee: U128,
+    pub fee_bps: u16,
+    /// Account receiving the withdraw fee.
+    pub collector_id: Option<AccountId>,
+}
+
+impl Default for WithdrawFeeSchedule {
+    fn default() -> Self {
+        Self {
+            flat_fee: 0.into(),
+            fee_bps: 0,
+            collector_id: None,
+        }
+    }
+}
+
+impl WithdrawFeeSchedule {
+    pub fn validate(&self) -> Result<(), error::WithdrawFeeError> {
+        if self.fee_bps > MAX_BASIS_POINTS {
+            return Err(error::WithdrawFeeError::InvalidBasisPoints);
+        }
+        if (self.flat_fee.0 > 0 || self.fee_bps > 0) && self.collector_id.is_none() {
+            return Err(error::WithdrawFeeError::MissingCollector);
+        }
+        Ok(())
+    }
+
+    /// Fee for the withdrawn amount. The fee should be lower than the amount.
+    pub fn fee_for(&self, amount: NEP141Wei) -> Result<NEP141Wei, error::WithdrawFeeError> {
+        let amount = amount.as_u128();
+        let fee = self
+            .flat_fee
+            .0
+            .saturating_add(basis_points(amount, self.fee_bps));
+        if fee > 0 && fee >= amount {
+            return Err(error::WithdrawFeeError::FeeTooHigh);
+        }
+        Ok(NEP141Wei::new(fee))
+    }
+}
+
+#[ext_contract(ext_withdraw_fee)]
+pub trait WithdrawFeeManagement {
+    /// Set the withdraw fee schedule.
+    fn set_withdraw_fee_schedule(&mut self, schedule: WithdrawFeeSchedule);
+
+    fn get_withdraw_fee_schedule(&self) -> WithdrawFeeSchedule;
+}
+
+pub mod error {
+    use crate::errors;
+
+    #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+    pub enum WithdrawFeeError {
+        FeeTooHigh,
+        InvalidBasisPoints,
+        MissingCollector,
+    }
+
+    impl AsRef<[u8]> for WithdrawFeeError {
+        fn as_ref(&self) -> &[u8] {
+            match self {
+                Self::FeeTooHigh => errors::ERR_WITHDRAW_FEE_TOO_HIGH,
+                Self::InvalidBasisPoints => errors::ERR_INVALID_BASIS_POINTS,
+                Self::MissingCollector => errors::ERR_FEE_COLLECTOR_NOT_SET,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collector() -> AccountId {
+        AccountId::new_unchecked("collector".to_string())
+    }
+
+    #[test]
+    fn test_no_fee() {
+        let schedule = WithdrawFeeSchedule::default();
+        assert_eq!(
+            schedule.fee_for(NEP141Wei::new(1)).unwrap(),
+            NEP141Wei::new(0)
+        );
+    }
+
+    #[test]
+    fn test_flat_and_bps_fee() {
+        let schedule = WithdrawFeeSchedule {
+            flat_fee: 10.into(),
+            fee_bps: 100,
+            collector_id: Some(collector()),
+        };
+        assert_eq!(
+            schedule.fee_for(NEP141Wei::new(1000)).unwrap(),
+            NEP141Wei::new(20)
+        );
+        assert!(matches!(
+            schedule.fee_for(NEP141Wei::new(10)),
+            Err(error::WithdrawFeeError::FeeTooHigh)
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut schedule = WithdrawFeeSchedule {
+            flat_fee: 10.into(),
+            ..WithdrawFeeSchedule::default()
+        };
+        assert!(matches!(
+            schedule.validate(),
+            Err(error::WithdrawFeeError::MissingCollector)
+        ));
+        schedule.collector_id = Some(collector());
+        assert!(schedule.validate().is_ok());
+        schedule.fee_bps = MAX_BASIS_POINTS + 1;
+        assert!(matches!(
+            schedule.validate(),
+            Err(error::WithdrawFeeError::InvalidBasisPoints)
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::WithdrawResult;
+use near_sdk::{
+    borsh, ext_contract,
+    json_types::{U128, U64},
+    serde::Serialize,
+    AccountId, Gas,
+};
+
+pub const GAS_FOR_RESOLVE_WITHDRAW_CALL: Gas = Gas(5_000_000_000_000);
+pub const GAS_FOR_WITHDRAW_CALL: Gas = Gas(25_000_000_000_000);
+
+/// JSON view of `WithdrawResult`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawResultView {
+    pub amount: U128,
+    pub recipient_id: String,
+    pub eth_custodian_address: String,
+    pub nonce: U64,
+    pub fee: U128,
+}
+
+impl From<&WithdrawResult> for WithdrawResultView {
+    fn from(result: &WithdrawResult) -> Self {
+        Self {
+            amount: result.amount.as_u128().into(),
+            recipient_id: result.recipient_id.encode(),
+            eth_custodian_address: result.eth_custodian_address.encode(),
+            nonce: result.nonce.into(),
+            fee: result.fee.as_u128().into(),
+        }
+    }
+}
+
+#[ext_contract(ext_withdraw_receiver)]
+pub trait WithdrawReceiver {
+    /// Called by the connector after `withdraw_call` was initiated by `sender_id`.
+    /// The tokens were already burned, so the result of the call doesn't affect
+    /// the withdrawal.
+    ///
+    /// Arguments:
+    /// - `sender_id` - the account ID that initiated the withdrawal.
+    /// - `result` - the withdrawal result.
+    /// - `msg` - a string message that was passed with this withdraw call.
+    fn on_withdraw(&mut self, sender_id: AccountId, result: WithdrawResultView, msg: String);
+}
+
+#[ext_contract(ext_withdraw_resolver)]
+pub trait WithdrawCallResolver {
+    /// Emit the `withdraw_call` event and return the withdrawal result.
+    #[result_serializer(borsh)]
+    fn withdraw_call_resolve(
+        &mut self,
+        #[serializer(borsh)] sender_id: AccountId,
+        #[serializer(borsh)] receiver_id: AccountId,
+        #[serializer(borsh)] result: WithdrawResult,
+    ) -> WithdrawResult;
+}
@@ -0,0 +1,190 @@
+use aurora_engine_types::types::{Address, NEP141Wei};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    ext_contract,
+    json_types::{U128, U64},
+    serde::Serialize,
+    AccountId, BlockHeight, IntoStorageKey,
+};
+
+/// Default page size of the `withdrawals_of` view.
+pub const DEFAULT_WITHDRAWALS_OF_LIMIT: u64 = 50;
+/// Maximum number of withdrawals kept per account. The oldest records are dropped.
+pub const MAX_WITHDRAWALS_PER_ACCOUNT: u64 = 100;
+
+/// Withdrawal record.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawalRecord {
+    pub nonce: u64,
+    pub sender_id: AccountId,
+    pub recipient_address: Address,
+    pub amount: NEP141Wei,
+    pub fee: NEP141Wei,
+    pub eth_custodian_address: Address,
+    pub block_height: BlockHeight,
+}
+
+/// JSON view of `WithdrawalRecord`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawalRecordView {
+    pub nonce: U64,
+    pub sender_id: AccountId,
+    pub recipient_address: String,
+    pub amount: U128,
+    pub fee: U128,
+    pub eth_custodian_address: String,
+    pub block_height: U64,
+}
+
+impl From<WithdrawalRecord> for WithdrawalRecordView {
+    fn from(record: WithdrawalRecord) -> Self {
+        Self {
+            nonce: record.nonce.into(),
+            sender_id: record.sender_id,
+            recipient_address: record.recipient_address.encode(),
+            amount: record.amount.as_u128().into(),
+            fee: record.fee.as_u128().into(),
+            eth_custodian_address: record.eth_custodian_address.encode(),
+            block_height: record.block_height.into(),
+        }
+    }
+}
+
+/// Ledger of withdrawals by nonce.
+/// Each account keeps up to `MAX_WITHDRAWALS_PER_ACCOUNT` latest withdrawals, so recording
+/// a withdrawal costs the same gas and storage regardless of the account history.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct WithdrawalLedger {
+    /// Nonce of the next withdrawal
+    pub next_nonce: u64,
+    pub records: LookupMap<u64, WithdrawalRecord>,
+    /// Withdrawal nonces by the account and the index of the withdrawal of the account
+    pub accounts: LookupMap<(AccountId, u64), u64>,
+    /// Number of withdrawals recorded for the account
+    pub account_counters: LookupMap<AccountId, u64>,
+}
+
+impl WithdrawalLedger {
+    pub fn new<S: IntoStorageKey>(
+        prefix_records: S,
+        prefix_accounts: S,
+        prefix_account_counters: S,
+    ) -> Self {
+        Self {
+            next_nonce: 0,
+            records: LookupMap::new(prefix_records),
+            accounts: LookupMap::new(prefix_accounts),
+            account_counters: LookupMap::new(prefix_account_counters),
+        }
+    }
+
+    /// Allocate the nonce for the next withdrawal.
+    pub fn next_nonce(&mut self) -> u64 {
+        let nonce = self.next_nonce;
+        self.next_nonce += 1;
+        nonce
+    }
+
+    /// Record the withdrawal with the allocated nonce and drop the oldest withdrawal
+    /// of the account over the limit.
+    pub fn record(&mut self, record: &WithdrawalRecord) {
+        let count = self.count_of(&record.sender_id);
+        self.records.insert(&record.nonce, record);
+        self.accounts
+            .insert(&(record.sender_id.clone(), count), &record.nonce);
+        self.account_counters
+            .insert(&record.sender_id, &(count + 1));
+        if count >= MAX_WITHDRAWALS_PER_ACCOUNT {
+            let dropped_key = (
+                record.sender_id.clone(),
+                count - MAX_WITHDRAWALS_PER_ACCOUNT,
+            );
+            if let Some(dropped_nonce) = self.accounts.remove(&dropped_key) {
+                self.records.remove(&dropped_nonce);
+            }
+        }
+    }
+
+    pub fn get(&self, nonce: u64) -> Option<WithdrawalRecord> {
+        self.records.get(&nonce)
+    }
+
+    fn count_of(&self, account_id: &AccountId) -> u64 {
+        self.account_counters.get(account_id).unwrap_or(0)
+    }
+
+    /// Return withdrawals of the account from the oldest to the newest.
+    pub fn withdrawals_of(
+        &self,
+        account_id: &AccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<WithdrawalRecord> {
+        let count = self.count_of(account_id);
+        let first = count.saturating_sub(MAX_WITHDRAWALS_PER_ACCOUNT);
+        let start = first.saturating_add(from_index).min(count);
+        let end = start.saturating_add(limit).min(count);
+        (start..end)
+            .filter_map(|index| self.accounts.get(&(account_id.clone(), index)))
+            .filter_map(|nonce| self.records.get(&nonce))
+            .collect()
+    }
+}
+
+#[ext_contract(ext_withdrawal_ledger)]
+pub trait WithdrawalLedgerView {
+    fn get_withdrawal(&self, nonce: U64) -> Option<WithdrawalRecordView>;
+
+    /// Return withdrawals of the account from the oldest to the newest.
+    /// Only the latest `MAX_WITHDRAWALS_PER_ACCOUNT` withdrawals are kept.
+    fn withdrawals_of(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<WithdrawalRecordView>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::VMContextBuilder;
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_withdrawals_limit() {
+        testing_env!(VMContextBuilder::new().build());
+        let alice: AccountId = "alice".parse().unwrap();
+        let mut ledger = WithdrawalLedger::new(b"r".to_vec(), b"a".to_vec(), b"c".to_vec());
+        for _ in 0..MAX_WITHDRAWALS_PER_ACCOUNT + 2 {
+            let nonce = ledger.next_nonce();
+            ledger.record(&WithdrawalRecord {
+                nonce,
+                sender_id: alice.clone(),
+                recipient_address: Address::default(),
+                amount: NEP141Wei::new(100),
+                fee: NEP141Wei::new(0),
+                eth_custodian_address: Address::default(),
+                block_height: 1,
+            });
+        }
+
+        // The two oldest withdrawals are dropped
+        assert!(ledger.get(1).is_none());
+        assert!(ledger.get(2).is_some());
+        let nonces: Vec<u64> = ledger
+            .withdrawals_of(&alice, 0, 3)
+            .iter()
+            .map(|record| record.nonce)
+            .collect();
+        assert_eq!(nonces, [2, 3, 4]);
+        let records = ledger.withdrawals_of(&alice, MAX_WITHDRAWALS_PER_ACCOUNT - 1, 10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].nonce, MAX_WITHDRAWALS_PER_ACCOUNT + 1);
+        assert!(ledger
+            .withdrawals_of(&alice, MAX_WITHDRAWALS_PER_ACCOUNT, 10)
+            .is_empty());
+    }
+}